@@ -0,0 +1,51 @@
+//! Adjusting a large min bucket in one batch with `adjust_first`, against
+//! mutating each of its entries through `first_mut`.
+//!
+//! Run with `cargo bench --bench adjust_first`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use valord_map::ValordMap;
+
+const BUCKET: usize = 50_000;
+const OTHERS: usize = 1_000;
+const CYCLES: usize = 20;
+
+/// Every 16th entry of the min bucket moves up, the rest keep their value.
+fn touch(k: &usize, v: &mut usize) {
+    if k % 16 == 1 {
+        *v += 1;
+    }
+}
+
+fn run(batched: bool) -> Duration {
+    let mut base = ValordMap::new();
+    (0..BUCKET).for_each(|k| base.insert(k, 0));
+    (BUCKET..BUCKET + OTHERS).for_each(|k| base.insert(k, k));
+
+    (0..CYCLES)
+        .map(|_| {
+            let mut valord = base.clone();
+            let start = Instant::now();
+            if batched {
+                valord.adjust_first(touch);
+            } else {
+                valord.first_mut().iter_mut().for_each(|entry| {
+                    let (k, v) = entry.get_mut_with_key();
+                    touch(k, v);
+                });
+            }
+            let elapsed = start.elapsed();
+            black_box(valord);
+            elapsed
+        })
+        .sum()
+}
+
+fn main() {
+    let one_by_one = run(false);
+    let batched = run(true);
+    println!("first_mut:    {one_by_one:?}");
+    println!("adjust_first: {batched:?}");
+}
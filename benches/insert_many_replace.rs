@@ -0,0 +1,45 @@
+//! Batch upserts that report the displaced values: `insert_many_replace`,
+//! against looking each old value up with `get` before `insert`.
+//!
+//! Run with `cargo bench --bench insert_many_replace`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use valord_map::ValordMap;
+
+const KEYS: usize = 100_000;
+const CYCLES: usize = 10;
+
+fn run(batched: bool) -> Duration {
+    let mut valord = ValordMap::new();
+    (0..KEYS / 2).for_each(|k| valord.insert(k, k));
+
+    (0..CYCLES)
+        .map(|cycle| {
+            let pairs = (0..KEYS).map(|k| (k, k + cycle));
+            let start = Instant::now();
+            let replaced: Vec<_> = if batched {
+                valord.insert_many_replace(pairs)
+            } else {
+                pairs
+                    .map(|(k, v)| {
+                        let old = valord.get(&k).cloned();
+                        valord.insert(k, v);
+                        (k, old)
+                    })
+                    .collect()
+            };
+            let elapsed = start.elapsed();
+            black_box(replaced);
+            elapsed
+        })
+        .sum()
+}
+
+fn main() {
+    let one_by_one = run(false);
+    let batched = run(true);
+    println!("get + insert:        {one_by_one:?}");
+    println!("insert_many_replace: {batched:?}");
+}
@@ -0,0 +1,40 @@
+//! Fill/drain cycles over a fixed set of tiers, with and without
+//! `prewarm_buckets`.
+//!
+//! Run with `cargo bench --bench prewarm`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use valord_map::ValordMap;
+
+const TIERS: usize = 64;
+const PER_TIER: usize = 256;
+const CYCLES: usize = 50;
+
+fn fill_and_drain(valord: &mut ValordMap<usize, usize, usize>) {
+    (0..TIERS * PER_TIER).for_each(|k| valord.insert(k, k % TIERS));
+    (0..TIERS * PER_TIER).for_each(|k| {
+        black_box(valord.remove(&k));
+    });
+}
+
+fn run(prewarm: bool) -> Duration {
+    let mut valord = ValordMap::new();
+    if prewarm {
+        valord.prewarm_buckets(0..TIERS);
+    }
+    // The first cycle grows every bucket to its working size.
+    fill_and_drain(&mut valord);
+
+    let start = Instant::now();
+    (0..CYCLES).for_each(|_| fill_and_drain(&mut valord));
+    start.elapsed()
+}
+
+fn main() {
+    let cold = run(false);
+    let prewarmed = run(true);
+    println!("cold:      {cold:?}");
+    println!("prewarmed: {prewarmed:?}");
+}
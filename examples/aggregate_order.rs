@@ -0,0 +1,45 @@
+use valord_map::{OrdBy, ValordMap};
+
+#[derive(Debug, PartialEq)]
+struct Basket {
+    prices: Vec<u32>,
+}
+
+// `ord_by` returns an owned `Target`, so it can compute an aggregate of the
+// value's contents. It is recomputed whenever the map re-sorts an entry.
+impl OrdBy for Basket {
+    type Target = u32;
+
+    fn ord_by(&self) -> Self::Target {
+        self.prices.iter().sum()
+    }
+}
+
+fn main() {
+    let mut baskets = ValordMap::new();
+    baskets.insert("qians", Basket { prices: vec![3, 4] });
+    baskets.insert(
+        "tedious",
+        Basket {
+            prices: vec![1, 1, 1],
+        },
+    );
+    baskets.insert("xuandu", Basket { prices: vec![10] });
+
+    let totals: Vec<_> = baskets.iter().map(|(k, b)| (*k, b.ord_by())).collect();
+    assert_eq!(totals, vec![("tedious", 3), ("qians", 7), ("xuandu", 10)]);
+
+    // Mutating through `get_mut` re-sorts by the new sum once the entry is dropped.
+    baskets.get_mut(&"tedious").unwrap().prices.push(20);
+    assert_eq!(baskets.last()[0].0, &"tedious");
+
+    // So does `modify`.
+    assert!(baskets.modify(&"xuandu", |b| b.prices.clear()));
+    assert_eq!(baskets.first()[0].0, &"xuandu");
+
+    // Range bounds are in terms of the aggregate.
+    let between: Vec<_> = baskets.range(5..=20).map(|(k, _)| *k).collect();
+    assert_eq!(between, vec!["qians"]);
+
+    println!("baskets: {:?}", baskets.iter().collect::<Vec<_>>());
+}
@@ -0,0 +1,48 @@
+use std::cmp::Reverse;
+
+use valord_map::{OrdBy, ValordMap};
+
+#[derive(Debug, PartialEq)]
+struct Player {
+    name: String,
+    score: u32,
+}
+
+impl OrdBy for Player {
+    type Target = Reverse<u32>;
+
+    fn ord_by(&self) -> Self::Target {
+        Reverse(self.score)
+    }
+}
+
+fn main() {
+    let mut players = ValordMap::new();
+    for (id, name, score) in [(1, "qians", 30), (2, "tedious", 50), (3, "xuandu", 10)] {
+        players.insert(
+            id,
+            Player {
+                name: name.to_string(),
+                score,
+            },
+        );
+    }
+
+    // `first` is now the highest score.
+    let best = players.first();
+    assert_eq!(best.len(), 1);
+    assert_eq!(best[0].1.name, "tedious");
+
+    // `iter` walks from the highest score to the lowest.
+    let scores: Vec<_> = players.iter().map(|(_, p)| p.score).collect();
+    assert_eq!(scores, vec![50, 30, 10]);
+
+    // Range bounds are `Reverse` values too: `Reverse(40)..` means "score <= 40".
+    let at_most_40: Vec<_> = players
+        .range(Reverse(40)..)
+        .map(|(_, p)| p.name.as_str())
+        .collect();
+    assert_eq!(at_most_40, vec!["qians", "xuandu"]);
+
+    println!("players: {:?}", players.iter().collect::<Vec<_>>());
+}
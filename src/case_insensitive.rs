@@ -0,0 +1,62 @@
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+};
+
+/// A string wrapper that compares, and hashes, by its lowercased characters.
+///
+/// Use it as an [`OrdBy::Target`](crate::OrdBy::Target) to order entries by name
+/// ignoring case. Comparison walks both strings' [`char::to_lowercase`] sequences
+/// side by side, so it does not allocate. Strings that differ only in case are
+/// equal and end up in the same bucket. This is plain lowercasing rather than full
+/// Unicode case folding, so `"Straße"` and `"STRASSE"` still differ.
+///
+/// # Example
+///
+/// ```
+/// use valord_map::{CaseInsensitive, ValordMap};
+///
+/// let mut valord = ValordMap::new();
+/// valord.insert(1, CaseInsensitive("bob"));
+/// valord.insert(2, CaseInsensitive("Alice"));
+/// valord.insert(3, CaseInsensitive("CHARLIE"));
+///
+/// assert_eq!(valord.keys().collect::<Vec<_>>(), vec![&2, &1, &3]);
+/// assert_eq!(CaseInsensitive("Bob"), CaseInsensitive("bOB"));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CaseInsensitive<S>(pub S);
+
+impl<S: AsRef<str>> CaseInsensitive<S> {
+    fn lowercase_chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.0.as_ref().chars().flat_map(char::to_lowercase)
+    }
+}
+
+impl<S: AsRef<str>> PartialEq for CaseInsensitive<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.lowercase_chars().eq(other.lowercase_chars())
+    }
+}
+
+impl<S: AsRef<str>> Eq for CaseInsensitive<S> {}
+
+impl<S: AsRef<str>> PartialOrd for CaseInsensitive<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S: AsRef<str>> Ord for CaseInsensitive<S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.lowercase_chars().cmp(other.lowercase_chars())
+    }
+}
+
+impl<S: AsRef<str>> Hash for CaseInsensitive<S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.lowercase_chars().for_each(|c| c.hash(state));
+        // Terminate like `str` does, so ("ab", "c") and ("a", "bc") hash apart.
+        state.write_u8(0xff);
+    }
+}
@@ -1,28 +1,32 @@
 mod raw;
 pub use raw::RawEntry;
 
-use crate::OrdBy;
+mod vacant;
+pub use vacant::VacantEntry;
+
+use crate::{ByOrdBy, OrdByFn};
+
 use std::hash::Hash;
 
 /// Entry for an existing key-value pair in an [`ValordMap`][crate::ValordMap]
 /// or a vacant location to insert one.
-pub enum Entry<'v, T, K, V>
+pub enum Entry<'v, T, K, V, O = ByOrdBy>
 where
     T: Ord + Clone,
     K: Hash + Eq,
-    V: OrdBy<Target = T>,
+    O: OrdByFn<V, T>,
 {
     /// Existing slot with equivalent key.
-    Occupied(RawEntry<'v, T, K, V>),
+    Occupied(RawEntry<'v, T, K, V, O>),
     /// Vacant slot (no equivalent key in the map).
-    Vacant(RawEntry<'v, T, K, V>),
+    Vacant(VacantEntry<'v, T, K, V, O>),
 }
 
-impl<'v, T, K, V> Entry<'v, T, K, V>
+impl<'v, T, K, V, O> Entry<'v, T, K, V, O>
 where
     T: Ord + Clone,
     K: Hash + Eq,
-    V: OrdBy<Target = T>,
+    O: OrdByFn<V, T>,
 {
     /// Inserts `default` value if the entry is vacant, and returns a mutable reference to the value.
     ///
@@ -39,10 +43,7 @@ where
     pub fn or_insert(&mut self, default: V) -> &mut V {
         match self {
             Entry::Occupied(entry) => entry.get_mut_with_key().1,
-            Entry::Vacant(entry) => {
-                entry.valord.free_indexs.pop_front();
-                entry.insert(default)
-            }
+            Entry::Vacant(entry) => entry.insert_with_key(|_| default),
         }
     }
 
@@ -62,10 +63,7 @@ where
     pub fn or_insert_with<F: FnOnce() -> V>(&mut self, default: F) -> &mut V {
         match self {
             Entry::Occupied(entry) => entry.get_mut_with_key().1,
-            Entry::Vacant(entry) => {
-                entry.valord.free_indexs.pop_front();
-                entry.insert(default())
-            }
+            Entry::Vacant(entry) => entry.insert_with_key(|_| default()),
         }
     }
 
@@ -85,10 +83,7 @@ where
     pub fn or_insert_with_key<F: FnOnce(&K) -> V>(&mut self, default: F) -> &mut V {
         match self {
             Entry::Occupied(entry) => entry.get_mut_with_key().1,
-            Entry::Vacant(entry) => {
-                entry.valord.free_indexs.pop_front();
-                entry.insert_with_key(default)
-            }
+            Entry::Vacant(entry) => entry.insert_with_key(default),
         }
     }
 
@@ -119,11 +114,12 @@ where
     }
 }
 
-impl<'v, T, K, V> Entry<'v, T, K, V>
+impl<'v, T, K, V, O> Entry<'v, T, K, V, O>
 where
     T: Ord + Clone,
     K: Hash + Eq,
-    V: OrdBy<Target = T> + Default,
+    O: OrdByFn<V, T>,
+    V: Default,
 {
     /// Inserts the default value if the entry is vacant, and returns a mutable reference to the value.
     ///
@@ -140,10 +136,7 @@ where
     pub fn or_default(&mut self) -> &mut V {
         match self {
             Entry::Occupied(entry) => entry.get_mut_with_key().1,
-            Entry::Vacant(entry) => {
-                entry.valord.free_indexs.pop_front();
-                entry.insert(V::default())
-            }
+            Entry::Vacant(entry) => entry.insert_with_key(|_| V::default()),
         }
     }
 }
@@ -1,142 +1,150 @@
-use crate::{OrdBy, ValordMap};
+use crate::{ByOrdBy, OrdByFn, ValordMap};
 
+use std::collections::{BTreeMap, HashSet};
 use std::hash::Hash;
+use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
 
-pub struct RawEntry<'v, T, K, V>
+type Buckets<T> = BTreeMap<T, HashSet<usize>>;
+
+pub struct RawEntry<'v, T, K, V, O = ByOrdBy>
 where
     T: Ord + Clone,
     K: Hash + Eq,
-    V: OrdBy<Target = T>,
+    O: OrdByFn<V, T>,
 {
-    pub(crate) index: usize,
-    pub(crate) valord: &'v mut ValordMap<T, K, V>,
+    index: usize,
+    /// Several entries of one map can be alive at once (`iter_mut`,
+    /// `get_disjoint_mut`, ...). Each borrows only its own key and value, all taken
+    /// in a single pass over the map, so no entry re-borrows another's slot.
+    key: &'v K,
+    value: &'v mut V,
+    /// The map's bucket indexes, shared by every entry made from the same borrow of
+    /// the map. They are only dereferenced inside [`indexs`](Self::indexs).
+    sorted_indexs: NonNull<Buckets<T>>,
+    prewarmed: NonNull<Buckets<T>>,
+    ord_by: &'v O,
+    _indexs: PhantomData<&'v mut Buckets<T>>,
 }
 
-impl<'v, T, K, V> RawEntry<'v, T, K, V>
+impl<'v, T, K, V, O> RawEntry<'v, T, K, V, O>
 where
     T: Ord + Clone,
     K: Hash + Eq,
-    V: OrdBy<Target = T>,
+    O: OrdByFn<V, T>,
 {
-    pub(crate) fn try_new_by_key<'a: 'v>(
-        valord: &'a mut ValordMap<T, K, V>,
-        key: &K,
-    ) -> Option<RawEntry<'v, T, K, V>> {
-        let (index, _, v) = valord.map.get_full(key)?;
-        v.as_ref()?;
-        Some(Self { index, valord })
+    /// The entries at `indexs`, in that order. Removed slots and repeated indexes
+    /// are skipped.
+    pub(crate) fn from_indexs(
+        valord: &'v mut ValordMap<T, K, V, O>,
+        indexs: impl IntoIterator<Item = usize>,
+    ) -> Vec<Self> {
+        let ValordMap {
+            map,
+            sorted_indexs,
+            prewarmed,
+            ord_by,
+            ..
+        } = valord;
+        let sorted_indexs = NonNull::from(sorted_indexs);
+        let prewarmed = NonNull::from(prewarmed);
+        let ord_by = &*ord_by;
+
+        let mut order: Vec<_> = indexs
+            .into_iter()
+            .enumerate()
+            .map(|(at, index)| (index, at))
+            .collect();
+        order.sort_unstable();
+
+        let mut entries: Vec<_> = std::iter::repeat_with(|| None).take(order.len()).collect();
+        let mut slots = map.iter_mut();
+        let mut next = 0;
+        for (index, at) in order {
+            let Some(skip) = index.checked_sub(next) else {
+                continue;
+            };
+            let Some((key, slot)) = slots.nth(skip) else {
+                break;
+            };
+            next = index + 1;
+            entries[at] = slot.as_mut().map(|value| Self {
+                index,
+                key,
+                value,
+                sorted_indexs,
+                prewarmed,
+                ord_by,
+                _indexs: PhantomData,
+            });
+        }
+        entries.into_iter().flatten().collect()
     }
 
-    pub(crate) fn try_new_by_index<'a: 'v>(
-        valord: &'a mut ValordMap<T, K, V>,
-        index: usize,
-    ) -> Option<RawEntry<'v, T, K, V>> {
-        valord.get_by_index(index)?;
-        Some(Self { index, valord })
+    /// The entry at `index`, or `None` if it holds no value.
+    pub(crate) fn try_new(valord: &'v mut ValordMap<T, K, V, O>, index: usize) -> Option<Self> {
+        Self::from_indexs(valord, [index]).pop()
     }
 
-    pub(crate) fn insert(&mut self, value: V) -> &mut V {
-        let v = self
-            .valord
-            .map
-            .get_index_mut(self.index)
-            .map(|(_, v)| {
-                if let Some(ref v) = v {
-                    ValordMap::<T, K, V>::remove_from_indexs(
-                        &mut self.valord.sorted_indexs,
-                        &v.ord_by(),
-                        self.index,
-                    );
-                }
-                v.insert(value)
-            })
-            .unwrap();
-        v
+    pub(crate) fn try_new_by_key(valord: &'v mut ValordMap<T, K, V, O>, key: &K) -> Option<Self> {
+        let index = valord.map.get_index_of(key)?;
+        Self::try_new(valord, index)
     }
 
-    pub(crate) fn insert_with_key<F: FnOnce(&K) -> V>(&mut self, value: F) -> &mut V {
-        let v = self
-            .valord
-            .map
-            .get_index_mut(self.index)
-            .map(|(k, v)| {
-                if let Some(ref v) = v {
-                    ValordMap::<T, K, V>::remove_from_indexs(
-                        &mut self.valord.sorted_indexs,
-                        &v.ord_by(),
-                        self.index,
-                    );
-                }
-                v.insert(value(k))
-            })
-            .unwrap();
-        v
+    fn indexs(&mut self) -> (&mut Buckets<T>, &mut Buckets<T>) {
+        // SAFETY: both pointers come from unique borrows of two distinct fields of the
+        // map, which last for `'v`. Entries made from them never hand out references
+        // into the indexes, and `&mut self` keeps this entry from reaching them again
+        // while these borrows are alive. So whichever entry calls this is the only one
+        // touching the indexes until it returns.
+        unsafe { (self.sorted_indexs.as_mut(), self.prewarmed.as_mut()) }
     }
 
     pub fn get_mut_with_key(&mut self) -> (&K, &mut V) {
-        let (k, v) = self
-            .valord
-            .map
-            .get_index_mut(self.index)
-            .map(|(k, v)| (k, v.as_mut().unwrap()))
-            .unwrap();
-        ValordMap::<T, K, V>::remove_from_indexs(
-            &mut self.valord.sorted_indexs,
-            &v.ord_by(),
-            self.index,
-        );
+        let index = self.index;
+        let ord_by = self.ord_by.ord_by(&*self.value);
+        let (sorted_indexs, prewarmed) = self.indexs();
+        ValordMap::<T, K, V, O>::remove_from_indexs(sorted_indexs, prewarmed, &ord_by, index);
 
-        (k, v)
+        (self.key, &mut *self.value)
     }
 }
 
-impl<'a, T, K, V> Deref for RawEntry<'a, T, K, V>
+impl<'a, T, K, V, O> Deref for RawEntry<'a, T, K, V, O>
 where
     T: Ord + Clone,
     K: Hash + Eq,
-    V: OrdBy<Target = T>,
+    O: OrdByFn<V, T>,
 {
     type Target = V;
 
     fn deref(&self) -> &Self::Target {
-        // Safety: if value is not exist, try_new() will return None
-        self.valord
-            .get_by_index(self.index)
-            .map(|(_, v)| v)
-            .unwrap()
+        self.value
     }
 }
 
-impl<'a, T, K, V> DerefMut for RawEntry<'a, T, K, V>
+impl<'a, T, K, V, O> DerefMut for RawEntry<'a, T, K, V, O>
 where
     T: Ord + Clone,
     K: Hash + Eq,
-    V: OrdBy<Target = T>,
+    O: OrdByFn<V, T>,
 {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        // Safety: if value is not exist, try_new() will return None
         self.get_mut_with_key().1
     }
 }
 
-impl<'a, T, K, V> Drop for RawEntry<'a, T, K, V>
+impl<'a, T, K, V, O> Drop for RawEntry<'a, T, K, V, O>
 where
     T: Ord + Clone,
     K: Hash + Eq,
-    V: OrdBy<Target = T>,
+    O: OrdByFn<V, T>,
 {
     fn drop(&mut self) {
-        if let Some(ord_by) = self
-            .valord
-            .get_by_index(self.index)
-            .map(|(_, v)| v.ord_by().clone())
-        {
-            self.valord
-                .sorted_indexs
-                .entry(ord_by)
-                .or_default()
-                .insert(self.index);
-        };
+        let index = self.index;
+        let ord_by = self.ord_by.ord_by(&*self.value);
+        let (sorted_indexs, prewarmed) = self.indexs();
+        ValordMap::<T, K, V, O>::bucket_mut(sorted_indexs, prewarmed, ord_by).insert(index);
     }
 }
@@ -0,0 +1,73 @@
+use crate::{ByOrdBy, OrdByFn, ValordMap};
+
+use std::hash::Hash;
+
+/// A vacant slot in a [`ValordMap`], as returned by [`Entry::Vacant`][crate::Entry::Vacant].
+///
+/// The map is left untouched until a value is actually inserted, so dropping a
+/// vacant entry without inserting leaves the map exactly as it was.
+pub struct VacantEntry<'v, T, K, V, O = ByOrdBy>
+where
+    T: Ord + Clone,
+    K: Hash + Eq,
+    O: OrdByFn<V, T>,
+{
+    /// The key to insert, `None` once the value has been inserted.
+    pub(crate) key: Option<K>,
+    /// The slot left behind by a previous removal of `key`, or the slot of the
+    /// inserted value.
+    pub(crate) index: Option<usize>,
+    pub(crate) valord: &'v mut ValordMap<T, K, V, O>,
+}
+
+impl<'v, T, K, V, O> VacantEntry<'v, T, K, V, O>
+where
+    T: Ord + Clone,
+    K: Hash + Eq,
+    O: OrdByFn<V, T>,
+{
+    pub(crate) fn new(valord: &'v mut ValordMap<T, K, V, O>, key: K, index: Option<usize>) -> Self {
+        Self {
+            key: Some(key),
+            index,
+            valord,
+        }
+    }
+
+    /// Gets a reference to the key that would be used when inserting a value.
+    pub fn key(&self) -> &K {
+        match (&self.key, self.index) {
+            (Some(key), _) => key,
+            (None, Some(index)) => self.valord.map.get_index(index).map(|(k, _)| k).unwrap(),
+            (None, None) => unreachable!("an inserted entry always has an index"),
+        }
+    }
+
+    pub(crate) fn insert_with_key<F: FnOnce(&K) -> V>(&mut self, value: F) -> &mut V {
+        if let Some(key) = self.key.take() {
+            let value = value(&key);
+            self.index = Some(self.valord.fill_vacant(key, self.index, value));
+        }
+
+        // Safety: the value was inserted above, and is re-sorted when the entry is dropped
+        self.index
+            .and_then(|index| self.valord.map.get_index_mut(index))
+            .and_then(|(_, v)| v.as_mut())
+            .unwrap()
+    }
+}
+
+impl<'a, T, K, V, O> Drop for VacantEntry<'a, T, K, V, O>
+where
+    T: Ord + Clone,
+    K: Hash + Eq,
+    O: OrdByFn<V, T>,
+{
+    fn drop(&mut self) {
+        if self.key.is_none() {
+            if let Some(index) = self.index {
+                self.valord.insert_to_indexs(index);
+            }
+        }
+    }
+}
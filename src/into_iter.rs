@@ -0,0 +1,38 @@
+/// An owning iterator over the entries of a [`ValordMap`][crate::ValordMap],
+/// order by value.ord_by().
+///
+/// Created by the [`IntoIterator`] impl of [`ValordMap`][crate::ValordMap].
+/// Entries not yet yielded are dropped together with the iterator.
+pub struct IntoIter<K, V> {
+    inner: std::vec::IntoIter<(K, V)>,
+}
+
+impl<K, V> IntoIter<K, V> {
+    pub(crate) fn new(sorted: Vec<(K, V)>) -> Self {
+        Self {
+            inner: sorted.into_iter(),
+        }
+    }
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V> std::iter::FusedIterator for IntoIter<K, V> {}
@@ -0,0 +1,195 @@
+use crate::ValordMap;
+
+use std::hash::Hash;
+use std::ops::RangeBounds;
+
+/// A map ordered by its keys, with the [`ValordMap`] layout underneath for hashed
+/// key lookup. Create one with [`KeyOrdered::new`] or [`ValordMap::by_key`].
+///
+/// Values can be of any type, as they take no part in the ordering. Each entry
+/// keeps a clone of its key as its ordering value. So every key is stored three
+/// times: as the hash map key, next to the value, and in the sorted index. Prefer
+/// cheap-to-clone keys.
+///
+/// # Example
+///
+/// ```
+/// use valord_map::KeyOrdered;
+///
+/// let mut map = KeyOrdered::new();
+/// map.insert(3, "c");
+/// map.insert(1, "a");
+/// map.insert(2, "b");
+///
+/// assert_eq!(map.iter().collect::<Vec<_>>(), vec![(&1, &"a"), (&2, &"b"), (&3, &"c")]);
+/// ```
+pub struct KeyOrdered<K, V>
+where
+    K: Ord + Clone + Hash,
+{
+    inner: ValordMap<K, K, (K, V), KeyOf<K, V>>,
+}
+
+type KeyOf<K, V> = fn(&(K, V)) -> &K;
+
+impl<K, V> KeyOrdered<K, V>
+where
+    K: Ord + Clone + Hash,
+{
+    pub fn new() -> Self {
+        Self {
+            inner: ValordMap::with_ord_by(|(k, _)| k),
+        }
+    }
+
+    /// Insert a key-value pair, returning the value previously stored for `key`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::KeyOrdered;
+    ///
+    /// let mut map = KeyOrdered::new();
+    /// assert_eq!(map.insert("qians", 1), None);
+    /// assert_eq!(map.insert("qians", 2), Some(1));
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.inner
+            ._insert(key.clone(), (key, value))
+            .map(|(_, old)| old)
+    }
+
+    /// Get the ref value by given key, or return `None` if not found
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::KeyOrdered;
+    ///
+    /// let mut map = KeyOrdered::new();
+    /// map.insert("qians", 1);
+    ///
+    /// assert_eq!(map.get(&"qians"), Some(&1));
+    /// assert_eq!(map.get(&"tedious"), None);
+    /// ```
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.inner.get(key).map(|(_, v)| v)
+    }
+
+    /// Get the mut value by given key. The value takes no part in the ordering, so
+    /// it can be changed in place.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::KeyOrdered;
+    ///
+    /// let mut map = KeyOrdered::new();
+    /// map.insert("qians", 1);
+    ///
+    /// *map.get_mut(&"qians").unwrap() += 1;
+    /// assert_eq!(map.get(&"qians"), Some(&2));
+    /// ```
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.inner
+            .map
+            .get_mut(key)
+            .and_then(|slot| slot.as_mut())
+            .map(|(_, v)| v)
+    }
+
+    /// Returns `true` if the map contains a value for the given key.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.inner.contains_key(key)
+    }
+
+    /// remove from KeyOrdered
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::KeyOrdered;
+    ///
+    /// let mut map = KeyOrdered::new();
+    /// map.insert(1, "a");
+    ///
+    /// assert_eq!(map.remove(&1), Some("a"));
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.inner.remove(key).map(|(_, v)| v)
+    }
+
+    /// Returns an iterator over the entries, order by key.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.inner.values().map(|(k, v)| (k, v))
+    }
+
+    /// Returns an iterator over the entries, reversed order by key.
+    pub fn rev_iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.inner.rev_values().map(|(k, v)| (k, v))
+    }
+
+    /// Returns the entry with the smallest key.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::KeyOrdered;
+    ///
+    /// let mut map = KeyOrdered::new();
+    /// map.insert("tedious", 2);
+    /// map.insert("qians", 1);
+    ///
+    /// assert_eq!(map.first(), Some((&"qians", &1)));
+    /// assert_eq!(map.last(), Some((&"tedious", &2)));
+    /// ```
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.iter().next()
+    }
+
+    /// Returns the entry with the largest key.
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.rev_iter().next()
+    }
+
+    /// Returns an iterator over the entries whose key is in `range`, order by key.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::KeyOrdered;
+    ///
+    /// let mut map = KeyOrdered::new();
+    /// (0..10).for_each(|i| {
+    ///     map.insert(i, i * 10);
+    /// });
+    ///
+    /// assert_eq!(map.range(3..6).map(|(_, v)| *v).collect::<Vec<_>>(), vec![30, 40, 50]);
+    /// ```
+    pub fn range<R>(&self, range: R) -> impl Iterator<Item = (&K, &V)>
+    where
+        R: RangeBounds<K>,
+    {
+        self.inner.range(range).map(|(k, (_, v))| (k, v))
+    }
+
+    /// Return the number of key-value pairs in the map.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Return `true` if the map holds no entries.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
+
+impl<K, V> Default for KeyOrdered<K, V>
+where
+    K: Ord + Clone + Hash,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,23 +1,47 @@
-#![feature(let_chains)]
 #![doc = include_str!("../README.md")]
 #![doc(html_playground_url = "https://play.rust-lang.org")]
+mod case_insensitive;
+pub use case_insensitive::CaseInsensitive;
+
 mod order_by;
-pub use order_by::OrdBy;
+#[doc(hidden)]
+pub use order_by::__assert_ord_by_sound;
+pub use order_by::{check_ord_by, ByOrdBy, ByOrdByDesc, OrdBy, OrdByFn};
 
 mod entry;
-pub use entry::{Entry, RawEntry};
+pub use entry::{Entry, RawEntry, VacantEntry};
+
+mod into_iter;
+pub use into_iter::IntoIter;
+
+mod key_ordered;
+pub use key_ordered::KeyOrdered;
+
+mod running_order_stats;
+pub use running_order_stats::RunningOrderStats;
 
-use indexmap::{map::MutableKeys, IndexMap};
+mod snapshot;
+pub use snapshot::ValordSnapshot;
+
+use indexmap::{IndexMap, IndexSet};
 use std::{
-    collections::{BTreeMap, HashSet, VecDeque},
-    hash::Hash,
+    cmp::Reverse,
+    collections::{btree_map, hash_map::DefaultHasher, BTreeMap, HashMap, HashSet, VecDeque},
+    hash::{Hash, Hasher},
+    ops::Bound,
 };
 
-pub struct ValordMap<T, K, V: OrdBy<Target = T>> {
+type RawEntryPair<'v, T, K, V, O> = (RawEntry<'v, T, K, V, O>, RawEntry<'v, T, K, V, O>);
+
+pub struct ValordMap<T, K, V, O = ByOrdBy> {
     map: IndexMap<K, Option<V>>,
     sorted_indexs: BTreeMap<T, HashSet<usize>>,
 
-    free_indexs: VecDeque<usize>,
+    free_indexs: IndexSet<usize>,
+    /// Sets kept for [`prewarm_buckets`](ValordMap::prewarm_buckets) values, taken
+    /// over by their bucket when it's created and handed back when it empties.
+    prewarmed: BTreeMap<T, HashSet<usize>>,
+    ord_by: O,
 }
 
 impl<T, K, V> ValordMap<T, K, V>
@@ -27,10 +51,108 @@ where
     V: OrdBy<Target = T>,
 {
     pub fn new() -> Self {
+        Self::with_ord_by(ByOrdBy)
+    }
+}
+
+impl<K, V> ValordMap<Reverse<V::Target>, K, V, ByOrdByDesc>
+where
+    K: Hash + Eq,
+    V: OrdBy,
+{
+    /// Create a ValordMap whose natural order is descending: entries are ordered
+    /// by `Reverse(value.ord_by())`.
+    ///
+    /// Every ordering method follows the reversed order, so `first` returns the
+    /// largest values and `iter` walks from high to low. `range` bounds are
+    /// `Reverse` values too, e.g. `Reverse(5)..` selects everything `<= 5`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::cmp::Reverse;
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new_desc();
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 3);
+    /// valord.insert("xuandu", 2);
+    ///
+    /// assert_eq!(valord.first(), vec![(&"tedious", &3)]);
+    /// assert_eq!(valord.values().collect::<Vec<_>>(), vec![&3, &2, &1]);
+    /// assert_eq!(valord.range(Reverse(2)..).count(), 2);
+    /// ```
+    pub fn new_desc() -> Self {
+        Self::with_ord_by(ByOrdByDesc)
+    }
+}
+
+impl<K, V> ValordMap<K, K, V>
+where
+    K: Ord + Clone + Hash,
+{
+    /// Create a map ordered by its keys instead of its values, as a [`KeyOrdered`].
+    ///
+    /// `V` takes no part in the ordering, so it needs no [`OrdBy`] impl.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut map = ValordMap::by_key();
+    /// map.insert("xuandu", vec![3]);
+    /// map.insert("qians", vec![1]);
+    ///
+    /// assert_eq!(map.first(), Some((&"qians", &vec![1])));
+    /// ```
+    pub fn by_key() -> KeyOrdered<K, V> {
+        KeyOrdered::new()
+    }
+}
+
+impl<T, K, V, O> ValordMap<T, K, V, O>
+where
+    T: Ord + Clone,
+    K: Hash + Eq,
+    O: OrdByFn<V, T>,
+{
+    /// Create a ValordMap ordered by the field `key_of` returns, instead of by [`OrdBy`].
+    ///
+    /// This allows ordering values of types that don't (and can't) implement `OrdBy`.
+    /// `key_of` is called everywhere `value.ord_by()` would be. It becomes the map's
+    /// [`OrdByFn`] type, so the calls are static and need no `Send`, `Sync` or
+    /// `'static` bound; methods that copy the ordering, like `clone`, need it to be
+    /// `Clone`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::ops::Range;
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new_by(|range: &Range<u32>| &range.end);
+    /// valord.insert("qians", 0..30);
+    /// valord.insert("tedious", 10..20);
+    /// valord.insert("xuandu", 20..25);
+    ///
+    /// assert_eq!(valord.keys().collect::<Vec<_>>(), vec![&"tedious", &"xuandu", &"qians"]);
+    /// assert_eq!(valord.range(..25).count(), 1);
+    /// ```
+    pub fn new_by(key_of: O) -> Self
+    where
+        O: Fn(&V) -> &T,
+    {
+        Self::with_ord_by(key_of)
+    }
+
+    fn with_ord_by(ord_by: O) -> Self {
         ValordMap {
             map: IndexMap::new(),
             sorted_indexs: BTreeMap::new(),
-            free_indexs: VecDeque::new(),
+            free_indexs: IndexSet::new(),
+            prewarmed: BTreeMap::new(),
+            ord_by,
         }
     }
 
@@ -58,30 +180,122 @@ where
     /// ```
     pub fn insert(&mut self, key: K, value: V) {
         // let key: Arc<_> = key.into();
-        self._insert(key, value)
+        self._insert(key, value);
+    }
+
+    /// Insert many key-value pairs into ValordMap, returning every key together
+    /// with the value it displaced (`None` if the key was not in the map).
+    ///
+    /// The result follows the input order, so a key repeated in `pairs` reports
+    /// the value inserted by its previous occurrence.
+    ///
+    /// Each pair costs the same as an [`insert`](ValordMap::insert). This only saves
+    /// the extra lookup and clone of fetching the old value first; see
+    /// `benches/insert_many_replace.rs`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 2);
+    ///
+    /// let replaced = valord.insert_many_replace([("tedious", 3), ("xuandu", 4), ("xuandu", 5)]);
+    /// assert_eq!(
+    ///     replaced,
+    ///     vec![("tedious", Some(2)), ("xuandu", None), ("xuandu", Some(4))]
+    /// );
+    /// assert_eq!(valord.len(), 3);
+    /// assert_eq!(valord.last(), vec![(&"xuandu", &5)]);
+    /// ```
+    pub fn insert_many_replace(
+        &mut self,
+        pairs: impl IntoIterator<Item = (K, V)>,
+    ) -> Vec<(K, Option<V>)>
+    where
+        K: Clone,
+    {
+        pairs
+            .into_iter()
+            .map(|(key, value)| (key.clone(), self._insert(key, value)))
+            .collect()
+    }
+
+    /// Insert a key-value pair only if the key is absent or `better(&new, &existing)`
+    /// returns `true`, returning whether the map was changed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// let higher = |new: &u32, old: &u32| new > old;
+    ///
+    /// assert!(valord.insert_keep_best("qians", 3, higher));
+    /// assert!(!valord.insert_keep_best("qians", 2, higher));
+    /// assert!(valord.insert_keep_best("qians", 5, higher));
+    /// assert_eq!(valord.get(&"qians"), Some(&5));
+    /// ```
+    pub fn insert_keep_best<F>(&mut self, key: K, value: V, better: F) -> bool
+    where
+        F: Fn(&V, &V) -> bool,
+    {
+        if self
+            .get(&key)
+            .is_some_and(|existing| !better(&value, existing))
+        {
+            return false;
+        }
+        self._insert(key, value);
+        true
     }
 
-    fn _insert(&mut self, key: K, value: V) {
-        let ord_by = value.ord_by();
+    fn _insert(&mut self, key: K, value: V) -> Option<V> {
+        let ord_by = self.ord_by.ord_by(&value);
 
-        let index = if let Some((index, _k, old_val)) = self.map.get_full_mut(&key) {
-            if let Some(old_val) = old_val {
-                Self::remove_from_indexs(&mut self.sorted_indexs, &old_val.ord_by(), index);
-                *old_val = value;
+        let (index, old) = match self.map.get_full_mut(&key) {
+            Some((index, _k, Some(old_val))) => {
+                Self::remove_from_indexs(
+                    &mut self.sorted_indexs,
+                    &mut self.prewarmed,
+                    &self.ord_by.ord_by(old_val),
+                    index,
+                );
+                (index, Some(std::mem::replace(old_val, value)))
             }
-            index
-        } else if let Some(free_index) = self.free_indexs.front().copied()
-            && let Some((k, v)) = self.map.get_index_mut2(free_index)
-        {
-            *k = key;
-            *v = Some(value);
-            self.free_indexs.pop_front();
-            free_index
-        } else {
-            self.map.insert_full(key, Some(value)).0
+            Some((index, _, None)) => (self.fill_vacant(key, Some(index), value), None),
+            None => (self.fill_vacant(key, None, value), None),
         };
 
-        self.sorted_indexs.entry(ord_by).or_default().insert(index);
+        Self::bucket_mut(&mut self.sorted_indexs, &mut self.prewarmed, ord_by).insert(index);
+        old
+    }
+
+    /// Puts `value` in the slot of a `key` that holds no value, returning the slot's
+    /// index. `sorted_indexs` is left to the caller.
+    ///
+    /// A removed key keeps its slot as a tombstone (the `IndexMap` hashes by key, so
+    /// the slot can't be handed to another key in place), and gets it back when it's
+    /// inserted again. A new key goes to the end, then is swapped into a freed slot if
+    /// there is one, which drops that slot's tombstone without moving any other entry.
+    fn fill_vacant(&mut self, key: K, tombstone: Option<usize>, value: V) -> usize {
+        if let Some(index) = tombstone {
+            self.map[index] = Some(value);
+            self.free_indexs.swap_remove(&index);
+            return index;
+        }
+
+        let (last, _) = self.map.insert_full(key, Some(value));
+        match self.free_indexs.pop() {
+            Some(index) => {
+                self.map.swap_remove_index(index);
+                index
+            }
+            None => last,
+        }
     }
 
     /// Get the given key’s corresponding entry in the map for insertion and/or
@@ -101,25 +315,41 @@ where
     ///
     /// assert_eq!(map.get(&"key"), Some(&"new value"));
     /// ```
-    pub fn entry(&mut self, key: K) -> Entry<'_, T, K, V> {
+    pub fn entry(&mut self, key: K) -> Entry<'_, T, K, V, O> {
         let valord = self;
         match valord.map.get_full(&key) {
-            Some((index, _, Some(_))) => return Entry::Occupied(RawEntry { index, valord }),
-            Some((index, _, None)) => return Entry::Vacant(RawEntry { index, valord }),
-            None => {}
+            Some((index, _, Some(_))) => Entry::Occupied(RawEntry::try_new(valord, index).unwrap()),
+            Some((index, _, None)) => Entry::Vacant(VacantEntry::new(valord, key, Some(index))),
+            None => Entry::Vacant(VacantEntry::new(valord, key, None)),
         }
+    }
 
-        let index = if let Some(free_index) = valord.free_indexs.front().copied() {
-            free_index
-        } else {
-            let index_entry = valord.map.entry(key);
-            let index = index_entry.index();
-            index_entry.or_insert(None);
-            valord.free_indexs.push_front(index);
-            index
-        };
-
-        Entry::Vacant(RawEntry { index, valord })
+    /// Returns `true` if [`entry`](ValordMap::entry) called with `key` would return
+    /// [`Entry::Occupied`], without moving the key.
+    ///
+    /// Removed keys report `false`, just like a fresh key would.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert(String::from("qians"), 1);
+    ///
+    /// let key = String::from("qians");
+    /// if valord.entry_occupied(&key) {
+    ///     valord.modify(&key, |v| *v += 1);
+    /// } else {
+    ///     valord.entry(key).or_insert(0);
+    /// }
+    /// assert_eq!(valord.get(&String::from("qians")), Some(&2));
+    ///
+    /// valord.remove(&String::from("qians"));
+    /// assert!(!valord.entry_occupied(&String::from("qians")));
+    /// ```
+    pub fn entry_occupied(&self, key: &K) -> bool {
+        matches!(self.map.get(key), Some(Some(_)))
     }
 
     /// Returns an iterator over the ValordMap.
@@ -144,8 +374,8 @@ where
     /// ```
     pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
         self.sorted_indexs
-            .iter()
-            .flat_map(|(_, indexs)| indexs.iter().filter_map(|index| self.get_by_index(*index)))
+            .values()
+            .flat_map(|indexs| indexs.iter().filter_map(|index| self.get_by_index(*index)))
     }
 
     /// Returns an reversesed iterator over the ValordMap.
@@ -206,18 +436,14 @@ where
     /// assert_eq!(max_list.len(), 1);
     /// assert_eq!(max_list, vec![(&"qians", &4)]);
     /// ```
-    pub fn iter_mut(&mut self) -> impl Iterator<Item = RawEntry<'_, T, K, V>> {
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = RawEntry<'_, T, K, V, O>> {
         let indexs: Vec<_> = self
             .sorted_indexs
-            .iter()
-            .flat_map(|(_, indexs)| indexs.iter())
+            .values()
+            .flat_map(|indexs| indexs.iter())
             .copied()
             .collect();
-        let valord: *mut ValordMap<T, K, V> = self;
-        indexs.into_iter().filter_map(move |index| {
-            let vm = unsafe { valord.as_mut()? };
-            vm.get_mut_by_index(index)
-        })
+        RawEntry::from_indexs(self, indexs).into_iter()
     }
 
     /// Returns an reversesed mut iterator over the ValordMap.
@@ -251,7 +477,7 @@ where
     /// assert_eq!(max_list.len(), 1);
     /// assert_eq!(max_list, vec![(&"xuandu", &0)]);
     /// ```
-    pub fn rev_iter_mut(&mut self) -> impl Iterator<Item = RawEntry<'_, T, K, V>> {
+    pub fn rev_iter_mut(&mut self) -> impl Iterator<Item = RawEntry<'_, T, K, V, O>> {
         let indexs: Vec<_> = self
             .sorted_indexs
             .iter()
@@ -259,11 +485,206 @@ where
             .flat_map(|(_, indexs)| indexs.iter())
             .copied()
             .collect();
-        let valord: *mut ValordMap<T, K, V> = self;
-        indexs.into_iter().filter_map(move |index| {
-            let vm = unsafe { valord.as_mut()? };
-            vm.get_mut_by_index(index)
-        })
+        RawEntry::from_indexs(self, indexs).into_iter()
+    }
+
+    /// Returns an iterator over the keys of the ValordMap, order by value.ord_by().
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 3);
+    /// valord.insert("tedious", 1);
+    /// valord.insert("xuandu", 2);
+    ///
+    /// let keys: Vec<_> = valord.keys().collect();
+    /// assert_eq!(keys, vec![&"tedious", &"xuandu", &"qians"]);
+    /// ```
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    /// Returns a reversed iterator over the keys of the ValordMap, order by value.ord_by().
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 3);
+    /// valord.insert("tedious", 1);
+    /// valord.insert("xuandu", 2);
+    ///
+    /// let keys: Vec<_> = valord.rev_keys().collect();
+    /// assert_eq!(keys, vec![&"qians", &"xuandu", &"tedious"]);
+    /// ```
+    pub fn rev_keys(&self) -> impl Iterator<Item = &K> {
+        self.rev_iter().map(|(k, _)| k)
+    }
+
+    /// Returns an iterator over the values of the ValordMap, order by value.ord_by().
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 3);
+    /// valord.insert("tedious", 1);
+    /// valord.insert("xuandu", 2);
+    ///
+    /// let values: Vec<_> = valord.values().collect();
+    /// assert_eq!(values, vec![&1, &2, &3]);
+    /// ```
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+
+    /// Returns a reversed iterator over the values of the ValordMap, order by value.ord_by().
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 3);
+    /// valord.insert("tedious", 1);
+    /// valord.insert("xuandu", 2);
+    ///
+    /// let values: Vec<_> = valord.rev_values().collect();
+    /// assert_eq!(values, vec![&3, &2, &1]);
+    /// ```
+    pub fn rev_values(&self) -> impl Iterator<Item = &V> {
+        self.rev_iter().map(|(_, v)| v)
+    }
+
+    /// Returns an iterator over the ValordMap yielding each entry with its 0-based rank,
+    /// order by value.ord_by().
+    ///
+    /// Entries sharing the same `ord_by()` value share the same rank, and the next
+    /// distinct value is ranked by the number of entries before it ("1224" ranking,
+    /// counted from 0), so a rank is always the number of strictly smaller entries.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 10);
+    /// valord.insert("tedious", 20);
+    /// valord.insert("xuandu", 20);
+    /// valord.insert("sheng", 30);
+    ///
+    /// let ranks: Vec<_> = valord.iter_ranked().map(|(rank, _, v)| (rank, *v)).collect();
+    /// assert_eq!(ranks, vec![(0, 10), (1, 20), (1, 20), (3, 30)]);
+    /// ```
+    pub fn iter_ranked(&self) -> impl Iterator<Item = (usize, &K, &V)> {
+        self.sorted_indexs
+            .values()
+            .scan(0, |rank, indexs| {
+                let bucket_rank = *rank;
+                *rank += indexs.len();
+                Some((bucket_rank, indexs))
+            })
+            .flat_map(|(rank, indexs)| {
+                self.iter_from_indexs(indexs)
+                    .map(move |(k, v)| (rank, k, v))
+            })
+    }
+
+    /// Folds the entries order by value.ord_by(), stopping at the first `Err`.
+    ///
+    /// Unlike collecting first, entries after the failing one are never visited.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 4);
+    /// valord.insert("tedious", 1);
+    /// valord.insert("xuandu", 2);
+    /// valord.insert("sheng", 8);
+    ///
+    /// // Take the cheapest items until the budget of 10 runs out.
+    /// let picked = valord.try_fold_sorted(Vec::new(), |mut picked, (k, v)| {
+    ///     if picked.iter().map(|(_, v)| v).sum::<u32>() + v > 10 {
+    ///         return Err(picked);
+    ///     }
+    ///     picked.push((*k, *v));
+    ///     Ok(picked)
+    /// });
+    /// assert_eq!(picked, Err(vec![("tedious", 1), ("xuandu", 2), ("qians", 4)]));
+    ///
+    /// let total = valord.try_fold_sorted(0, |sum, (_, v)| Ok::<_, ()>(sum + v));
+    /// assert_eq!(total, Ok(15));
+    /// ```
+    pub fn try_fold_sorted<B, E, F>(&self, init: B, f: F) -> Result<B, E>
+    where
+        F: FnMut(B, (&K, &V)) -> Result<B, E>,
+    {
+        self.iter().try_fold(init, f)
+    }
+
+    /// Returns a mut iterator over the values of the ValordMap, order by value.ord_by().
+    ///
+    /// Each item derefs to the value, and the map is re-sorted when the item is dropped,
+    /// same as [`iter_mut`](ValordMap::iter_mut).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 2);
+    /// valord.insert("xuandu", 3);
+    ///
+    /// valord.values_mut().for_each(|mut v| *v = 10 - *v);
+    ///
+    /// let values: Vec<_> = valord.values().collect();
+    /// assert_eq!(values, vec![&7, &8, &9]);
+    /// assert_eq!(valord.first(), vec![(&"xuandu", &7)]);
+    /// ```
+    pub fn values_mut(&mut self) -> impl Iterator<Item = RawEntry<'_, T, K, V, O>> {
+        self.iter_mut()
+    }
+
+    /// Returns a reversed mut iterator over the values of the ValordMap, order by value.ord_by().
+    ///
+    /// Each item derefs to the value, and the map is re-sorted when the item is dropped,
+    /// same as [`rev_iter_mut`](ValordMap::rev_iter_mut).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 2);
+    /// valord.insert("xuandu", 3);
+    ///
+    /// let mut iter = valord.rev_values_mut();
+    /// let mut max = iter.next().unwrap();
+    /// assert_eq!(*max, 3);
+    /// *max = 0;
+    /// drop(max);
+    /// drop(iter);
+    ///
+    /// assert_eq!(valord.first(), vec![(&"xuandu", &0)]);
+    /// ```
+    pub fn rev_values_mut(&mut self) -> impl Iterator<Item = RawEntry<'_, T, K, V, O>> {
+        self.rev_iter_mut()
     }
 
     /// Returns the first vector of key-value pairs in the map. The value in this pair is the minimum values in the map.
@@ -315,12 +736,13 @@ where
     /// let min_list = valord.first();
     /// assert!(min_list.iter().all(|(_, v)| **v == 0));
     /// ```
-    pub fn first_mut(&mut self) -> Vec<RawEntry<'_, T, K, V>> {
-        let valord: *mut ValordMap<T, K, V> = self;
-        self.sorted_indexs
+    pub fn first_mut(&mut self) -> Vec<RawEntry<'_, T, K, V, O>> {
+        let indexs = self
+            .sorted_indexs
             .first_key_value()
-            .map(|(_, indexs)| Self::iter_mut_from_indexs(valord, indexs.clone()).collect())
-            .unwrap_or_default()
+            .map(|(_, indexs)| indexs.clone())
+            .unwrap_or_default();
+        RawEntry::from_indexs(self, indexs)
     }
 
     /// Returns the last vector of key-value pairs in the map. The value in this pair is the maximum values in the map.
@@ -373,15 +795,20 @@ where
     /// assert_eq!(max_list.len(), 1);
     /// assert_eq!(max_list, vec![(&"xuandu", &3)]);
     /// ```
-    pub fn last_mut(&mut self) -> Vec<RawEntry<'_, T, K, V>> {
-        let valord: *mut ValordMap<T, K, V> = self;
-        self.sorted_indexs
+    pub fn last_mut(&mut self) -> Vec<RawEntry<'_, T, K, V, O>> {
+        let indexs = self
+            .sorted_indexs
             .last_key_value()
-            .map(|(_, indexs)| Self::iter_mut_from_indexs(valord, indexs.clone()).collect())
-            .unwrap_or_default()
+            .map(|(_, indexs)| indexs.clone())
+            .unwrap_or_default();
+        RawEntry::from_indexs(self, indexs)
     }
 
-    /// get range from ValordMap
+    /// Returns an entry with the smallest value.ord_by(), first inserting
+    /// `(key, make())` if the map is empty.
+    ///
+    /// `key` and `make` are unused when the map is not empty. Among tied entries,
+    /// which one is returned is unspecified.
     ///
     /// # Example
     ///
@@ -389,29 +816,255 @@ where
     /// use valord_map::ValordMap;
     ///
     /// let mut valord = ValordMap::new();
-    /// valord.insert("qians", 1);
-    /// valord.insert("tedious", 2);
-    /// valord.insert("sheng", 3);
-    /// valord.insert("xuandu", 4);
-    /// valord.insert("xuandu2", 5);
-    /// valord.insert("xuandu3", 6);
-    /// assert_eq!(valord.range(4..).last().unwrap(), (&"xuandu3", &6));
-    /// assert_eq!(
-    ///     valord
-    ///         .range(4..)
-    ///         .filter(|(_, v)| **v != 6)
-    ///         .last()
-    ///         .unwrap(),
-    ///     (&"xuandu2", &5)
-    /// );
+    /// assert_eq!(valord.first_or_insert_with("sentinel", || 0), (&"sentinel", &0));
+    ///
+    /// valord.insert("qians", -1);
+    /// assert_eq!(valord.first_or_insert_with("other", || 9), (&"qians", &-1));
+    /// assert_eq!(valord.len(), 2);
     /// ```
-    pub fn range<R>(&self, range: R) -> impl Iterator<Item = (&K, &V)>
+    pub fn first_or_insert_with<F>(&mut self, key: K, make: F) -> (&K, &V)
     where
-        R: std::ops::RangeBounds<V::Target>,
+        F: FnOnce() -> V,
     {
-        self.sorted_indexs
-            .range(range)
-            .flat_map(|(_, indexs)| self.iter_from_indexs(indexs))
+        if self.is_empty() {
+            self._insert(key, make());
+        }
+        self.iter().next().expect("the map is not empty")
+    }
+
+    /// Returns an entry with the largest value.ord_by(), first inserting
+    /// `(key, make())` if the map is empty.
+    ///
+    /// `key` and `make` are unused when the map is not empty. Among tied entries,
+    /// which one is returned is unspecified.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// assert_eq!(valord.last_or_insert_with("sentinel", || 0), (&"sentinel", &0));
+    ///
+    /// valord.insert("qians", 5);
+    /// assert_eq!(valord.last_or_insert_with("other", || 9), (&"qians", &5));
+    /// assert_eq!(valord.len(), 2);
+    /// ```
+    pub fn last_or_insert_with<F>(&mut self, key: K, make: F) -> (&K, &V)
+    where
+        F: FnOnce() -> V,
+    {
+        if self.is_empty() {
+            self._insert(key, make());
+        }
+        self.rev_iter().next().expect("the map is not empty")
+    }
+
+    /// Applies `f` to every entry in the first bucket (the smallest value.ord_by()),
+    /// then re-sorts them in one pass.
+    ///
+    /// Compared to mutating through [`first_mut`](ValordMap::first_mut), the bucket
+    /// is taken out once and entries whose ordering value didn't change stay in it;
+    /// see `benches/adjust_first.rs`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 1);
+    /// valord.insert("xuandu", 3);
+    ///
+    /// valord.adjust_first(|k, v| {
+    ///     if *k == "qians" {
+    ///         *v = 5;
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(valord.first(), vec![(&"tedious", &1)]);
+    /// assert_eq!(valord.last(), vec![(&"qians", &5)]);
+    /// ```
+    pub fn adjust_first<F>(&mut self, f: F)
+    where
+        F: FnMut(&K, &mut V),
+    {
+        if let Some(bucket) = self.sorted_indexs.pop_first() {
+            self.adjust_bucket(bucket, f);
+        }
+    }
+
+    /// Applies `f` to every entry in the last bucket (the largest value.ord_by()),
+    /// then re-sorts them in one pass.
+    ///
+    /// Compared to mutating through [`last_mut`](ValordMap::last_mut), the bucket
+    /// is taken out once and entries whose ordering value didn't change stay in it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 3);
+    /// valord.insert("xuandu", 3);
+    ///
+    /// valord.adjust_last(|_, v| *v -= 2);
+    ///
+    /// assert_eq!(valord.len(), 3);
+    /// assert_eq!(valord.first().len(), 3);
+    /// assert!(valord.values().all(|v| *v == 1));
+    /// ```
+    pub fn adjust_last<F>(&mut self, f: F)
+    where
+        F: FnMut(&K, &mut V),
+    {
+        if let Some(bucket) = self.sorted_indexs.pop_last() {
+            self.adjust_bucket(bucket, f);
+        }
+    }
+
+    /// Returns the key of the entry with the smallest value.ord_by(), breaking ties
+    /// with `tie`: among the tied keys, the one `tie` orders first is returned.
+    ///
+    /// Unlike [`first`](ValordMap::first), the result doesn't depend on the
+    /// unspecified order within a bucket.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("tedious", 1);
+    /// valord.insert("qians", 1);
+    /// valord.insert("xuandu", 2);
+    ///
+    /// assert_eq!(valord.min_key_by(|a, b| a.cmp(b)), Some(&"qians"));
+    /// assert_eq!(valord.min_key_by(|a, b| b.cmp(a)), Some(&"tedious"));
+    /// ```
+    pub fn min_key_by<F>(&self, tie: F) -> Option<&K>
+    where
+        F: Fn(&K, &K) -> std::cmp::Ordering,
+    {
+        let (_, indexs) = self.sorted_indexs.first_key_value()?;
+        self.iter_from_indexs(indexs)
+            .map(|(k, _)| k)
+            .min_by(|a, b| tie(a, b))
+    }
+
+    /// Returns the key of the entry with the largest value.ord_by(), breaking ties
+    /// with `tie`: among the tied keys, the one `tie` orders first is returned.
+    ///
+    /// Unlike [`last`](ValordMap::last), the result doesn't depend on the
+    /// unspecified order within a bucket.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 1);
+    /// valord.insert("xuandu", 2);
+    /// valord.insert("tedious", 2);
+    ///
+    /// assert_eq!(valord.max_key_by(|a, b| a.cmp(b)), Some(&"tedious"));
+    /// assert_eq!(valord.max_key_by(|a, b| a.len().cmp(&b.len())), Some(&"xuandu"));
+    /// ```
+    pub fn max_key_by<F>(&self, tie: F) -> Option<&K>
+    where
+        F: Fn(&K, &K) -> std::cmp::Ordering,
+    {
+        let (_, indexs) = self.sorted_indexs.last_key_value()?;
+        self.iter_from_indexs(indexs)
+            .map(|(k, _)| k)
+            .min_by(|a, b| tie(a, b))
+    }
+
+    /// Returns the `n`-th entry counting down from the largest value.ord_by()
+    /// (0 is a maximum), or `None` if the map has `n` entries or fewer.
+    ///
+    /// Whole buckets are skipped by their size, so only the bucket holding the
+    /// entry is walked. Which of several tied entries is returned is unspecified.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 2);
+    /// valord.insert("xuandu", 3);
+    ///
+    /// assert_eq!(valord.nth_from_end(0), Some((&"xuandu", &3)));
+    /// assert_eq!(valord.nth_from_end(1), Some((&"tedious", &2)));
+    /// assert_eq!(valord.nth_from_end(3), None);
+    /// ```
+    pub fn nth_from_end(&self, mut n: usize) -> Option<(&K, &V)> {
+        let (_, indexs) = self.sorted_indexs.iter().rev().find(|(_, indexs)| {
+            if n < indexs.len() {
+                return true;
+            }
+            n -= indexs.len();
+            false
+        })?;
+        self.iter_from_indexs(indexs).nth(n)
+    }
+
+    /// get range from ValordMap
+    ///
+    /// The bounds are compared against `value.ord_by()`, so with a
+    /// `Target = std::cmp::Reverse<_>` they must be written in `Reverse`
+    /// terms as well: `Reverse(4)..` selects every value `<= 4`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 2);
+    /// valord.insert("sheng", 3);
+    /// valord.insert("xuandu", 4);
+    /// valord.insert("xuandu2", 5);
+    /// valord.insert("xuandu3", 6);
+    /// assert_eq!(valord.range(4..).last().unwrap(), (&"xuandu3", &6));
+    /// assert_eq!(
+    ///     valord
+    ///         .range(4..)
+    ///         .filter(|(_, v)| **v != 6)
+    ///         .last()
+    ///         .unwrap(),
+    ///     (&"xuandu2", &5)
+    /// );
+    /// ```
+    ///
+    /// With a `Reverse` target:
+    ///
+    /// ```
+    /// use std::cmp::Reverse;
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", Reverse(1));
+    /// valord.insert("tedious", Reverse(2));
+    /// valord.insert("xuandu", Reverse(3));
+    ///
+    /// let range: Vec<_> = valord.range(Reverse(2)..).collect();
+    /// assert_eq!(range, vec![(&"tedious", &Reverse(2)), (&"qians", &Reverse(1))]);
+    /// ```
+    pub fn range<R>(&self, range: R) -> impl Iterator<Item = (&K, &V)>
+    where
+        R: std::ops::RangeBounds<T>,
+    {
+        self.sorted_indexs
+            .range(range)
+            .flat_map(|(_, indexs)| self.iter_from_indexs(indexs))
     }
 
     /// get range mut from ValordMap
@@ -446,9 +1099,9 @@ where
     ///     Some((&"xuandu", &8))
     /// );
     /// ```
-    pub fn range_mut<R>(&mut self, range: R) -> impl Iterator<Item = RawEntry<'_, T, K, V>>
+    pub fn range_mut<R>(&mut self, range: R) -> impl Iterator<Item = RawEntry<'_, T, K, V, O>>
     where
-        R: std::ops::RangeBounds<V::Target>,
+        R: std::ops::RangeBounds<T>,
     {
         let range: Vec<_> = self
             .sorted_indexs
@@ -456,14 +1109,15 @@ where
             .flat_map(|(_, indexs)| indexs.iter())
             .copied()
             .collect();
-        let valord: *mut ValordMap<T, K, V> = self;
-        range.into_iter().filter_map(move |index| {
-            let vm = unsafe { valord.as_mut()? };
-            vm.get_mut_by_index(index)
-        })
+        RawEntry::from_indexs(self, range).into_iter()
     }
 
-    /// Get the ref value by given key, or return `None` if not found
+    /// Removes the entries whose value.ord_by() falls in `range` and appends them
+    /// to `sink`, order by value.ord_by().
+    ///
+    /// Buckets are taken out of the sorted index one at a time, so nothing is
+    /// allocated besides growing `sink`. Like [`remove_min`](ValordMap::remove_min),
+    /// keys are cloned out since removed slots keep theirs.
     ///
     /// # Example
     ///
@@ -471,20 +1125,29 @@ where
     /// use valord_map::ValordMap;
     ///
     /// let mut valord = ValordMap::new();
-    /// valord.insert("key1", 1);
-    /// valord.insert("key2", 2);
-    /// valord.insert("key3", 3);
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 2);
+    /// valord.insert("xuandu", 3);
+    /// valord.insert("sheng", 4);
     ///
-    /// let mut val1 = valord.get(&"key2");
-    /// let mut val2 = valord.get(&"key4");
-    /// assert_eq!(val1.unwrap(), &2);
-    /// assert_eq!(val2, None);
+    /// let mut window = vec![("yu", 0)];
+    /// valord.drain_range_into(2..4, &mut window);
+    ///
+    /// assert_eq!(window, vec![("yu", 0), ("tedious", 2), ("xuandu", 3)]);
+    /// assert_eq!(valord.keys().collect::<Vec<_>>(), vec![&"qians", &"sheng"]);
     /// ```
-    pub fn get(&self, key: &K) -> Option<&V> {
-        self.map.get(key).and_then(|v| v.as_ref())
+    pub fn drain_range_into<R>(&mut self, range: R, sink: &mut Vec<(K, V)>)
+    where
+        K: Clone,
+        R: std::ops::RangeBounds<T>,
+    {
+        let bounds = (range.start_bound(), range.end_bound());
+        sink.reserve(self.range_count(bounds));
+        self.take_range(bounds, |_, k, v| sink.push((k.clone(), v)));
     }
 
-    /// Get the ref mut value by given key, or return `None` if not found
+    /// Removes the entries whose value.ord_by() falls in `range`, returning how many
+    /// were removed and the internal indexes they occupied, order by value.ord_by().
     ///
     /// # Example
     ///
@@ -492,21 +1155,28 @@ where
     /// use valord_map::ValordMap;
     ///
     /// let mut valord = ValordMap::new();
-    /// valord.insert("key1", 1);
-    /// valord.insert("key2", 2);
-    /// valord.insert("key3", 3);
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 2);
+    /// valord.insert("xuandu", 3);
     ///
-    /// let mut val = valord.get_mut(&"key2").unwrap();
-    /// *val = 4;
-    /// drop(val);
-    /// assert_eq!(valord.get(&"key2").unwrap(), &4);
-    /// assert_eq!(valord.last(), vec![(&"key2", &4)]);
+    /// assert_eq!(valord.delete_range(2..), (2, vec![1, 2]));
+    /// assert_eq!(valord.delete_range(5..), (0, vec![]));
+    /// assert_eq!(valord.keys().collect::<Vec<_>>(), vec![&"qians"]);
     /// ```
-    pub fn get_mut<'a>(&'a mut self, key: &K) -> Option<RawEntry<'a, T, K, V>> {
-        RawEntry::try_new_by_key(self, key)
+    pub fn delete_range<R>(&mut self, range: R) -> (usize, Vec<usize>)
+    where
+        R: std::ops::RangeBounds<T>,
+    {
+        let bounds = (range.start_bound(), range.end_bound());
+        let mut freed = Vec::with_capacity(self.range_count(bounds));
+        self.take_range(bounds, |index, _, _| freed.push(index));
+        (freed.len(), freed)
     }
 
-    /// Modify value in map, if exist return true, else return false
+    /// Returns the number of entries whose value.ord_by() falls in `range`.
+    ///
+    /// Only the matching buckets are visited, so the cost depends on how many
+    /// distinct ordering values are in the range, not on how many entries are.
     ///
     /// # Example
     ///
@@ -515,28 +1185,31 @@ where
     ///
     /// let mut valord = ValordMap::new();
     /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 2);
+    /// valord.insert("sheng", 2);
+    /// valord.insert("xuandu", 3);
     ///
-    /// assert!(valord.modify(&"qians", |v| *v = 2));
-    /// assert_eq!(valord.iter().next().unwrap(), (&"qians", &2));
+    /// assert_eq!(valord.range_count(2..), 3);
+    /// assert_eq!(valord.range_count(..2), 1);
+    /// assert_eq!(valord.range_count(4..), 0);
     /// ```
-    pub fn modify<F>(&mut self, key: &K, op: F) -> bool
+    pub fn range_count<R>(&self, range: R) -> usize
     where
-        F: Fn(&mut V),
+        R: std::ops::RangeBounds<T>,
     {
-        if let Some((index, _, v)) = Self::get_full_mut(&mut self.map, key) {
-            Self::remove_from_indexs(&mut self.sorted_indexs, &v.ord_by(), index);
-            op(v);
-            self.sorted_indexs
-                .entry(v.ord_by())
-                .or_default()
-                .insert(index);
-            true
-        } else {
-            false
-        }
+        self.sorted_indexs
+            .range(range)
+            .map(|(_, indexs)| indexs.len())
+            .sum()
     }
 
-    /// remove from ValordMap
+    /// Searches the sorted order for `target`, like [`slice::binary_search`] on the
+    /// output of [`iter`](ValordMap::iter) compared by value.ord_by().
+    ///
+    /// Returns `Ok(rank)` of the first entry whose value.ord_by() equals `target`, or
+    /// `Err(rank)` where such an entry would be inserted. The bucket is found in
+    /// O(log n), but the rank is summed over the buckets before it, so the cost also
+    /// grows with the number of distinct smaller ordering values.
     ///
     /// # Example
     ///
@@ -544,19 +1217,31 @@ where
     /// use valord_map::ValordMap;
     ///
     /// let mut valord = ValordMap::new();
-    /// valord.insert(1, "a");
-    /// valord.insert(2, "b");
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 3);
+    /// valord.insert("sheng", 3);
+    /// valord.insert("xuandu", 5);
     ///
-    /// let removed_value = valord.remove(&1);
-    /// assert_eq!(removed_value, Some("a"));
-    /// assert_eq!(valord.get(&1), None);
+    /// assert_eq!(valord.search_sorted(&3), Ok(1));
+    /// assert_eq!(valord.search_sorted(&5), Ok(3));
+    /// assert_eq!(valord.search_sorted(&4), Err(3));
+    /// assert_eq!(valord.search_sorted(&9), Err(4));
     /// ```
-    pub fn remove(&mut self, key: &K) -> Option<V> {
-        self.remove_entry(key).map(|v| v.1)
+    pub fn search_sorted(&self, target: &T) -> Result<usize, usize> {
+        let rank = self.range_count(..target);
+        if self.count_at(target) > 0 {
+            Ok(rank)
+        } else {
+            Err(rank)
+        }
     }
 
-    /// Removes a key from the map, returning the stored key and value if the
-    /// key was previously in the map.
+    /// Returns an iterator over the entries whose value.ord_by() starts with the
+    /// bytes of `prefix`, order by value.ord_by().
+    ///
+    /// For maps whose `ord_by()` target is byte ordered, like `Vec<u8>` (pass a
+    /// `[u8]` prefix) or `String` (pass a `str` prefix). The scan starts at
+    /// `prefix` and stops at the first ordering value that doesn't share it.
     ///
     /// # Example
     ///
@@ -564,25 +1249,34 @@ where
     /// use valord_map::ValordMap;
     ///
     /// let mut valord = ValordMap::new();
-    /// valord.insert(1, "a");
-    /// valord.insert(2, "b");
+    /// valord.insert(1, "apple".to_string());
+    /// valord.insert(2, "apricot".to_string());
+    /// valord.insert(3, "banana".to_string());
+    /// valord.insert(4, "ap".to_string());
     ///
-    /// let removed_entry = valord.remove_entry(&1);
-    /// assert_eq!(removed_entry, Some((&1, "a")));
-    /// assert_eq!(valord.get(&1), None);
+    /// let keys: Vec<_> = valord.prefix_range("ap").map(|(k, _)| *k).collect();
+    /// assert_eq!(keys, vec![4, 1, 2]);
+    ///
+    /// let mut bytes = ValordMap::new();
+    /// bytes.insert("a", vec![0x01, 0xff]);
+    /// bytes.insert("b", vec![0x01, 0xff, 0x00]);
+    /// bytes.insert("c", vec![0x02]);
+    /// assert_eq!(bytes.prefix_range(&[0x01, 0xff][..]).count(), 2);
     /// ```
-    pub fn remove_entry<'a>(&'a mut self, key: &'a K) -> Option<(&K, V)> {
-        if let Some((i, k, v)) = self.map.get_full_mut(key) {
-            if let Some(old) = v.take() {
-                self.free_indexs.push_back(i);
-                Self::remove_from_indexs(&mut self.sorted_indexs, &old.ord_by(), i);
-                return Some((k, old));
-            };
-        }
-        None
+    pub fn prefix_range<'a, Q>(&'a self, prefix: &'a Q) -> impl Iterator<Item = (&'a K, &'a V)>
+    where
+        T: std::borrow::Borrow<Q> + AsRef<[u8]>,
+        Q: Ord + AsRef<[u8]> + ?Sized,
+    {
+        use std::ops::Bound::{Included, Unbounded};
+
+        self.sorted_indexs
+            .range::<Q, _>((Included(prefix), Unbounded))
+            .take_while(move |(target, _)| target.as_ref().starts_with(prefix.as_ref()))
+            .flat_map(|(_, indexs)| self.iter_from_indexs(indexs))
     }
 
-    /// Return the number of key-value pairs in the map.
+    /// Returns `true` if the map contains a value for the given key.
     ///
     /// # Example
     ///
@@ -590,208 +1284,3401 @@ where
     /// use valord_map::ValordMap;
     ///
     /// let mut valord = ValordMap::new();
-    /// valord.insert(1, "a");
-    /// valord.insert(2, "b");
-    /// valord.insert(3, "c");
-    /// valord.insert(2, "d");
-    ///
-    /// assert_eq!(valord.len(), 3);
+    /// valord.insert("key1", 1);
+    /// valord.insert("key2", 2);
+    /// valord.remove(&"key2");
     ///
-    /// let removed_value = valord.remove(&1);
-    /// assert_eq!(removed_value, Some("a"));
-    /// assert_eq!(valord.len(), 2);
+    /// assert!(valord.contains_key(&"key1"));
+    /// assert!(!valord.contains_key(&"key2"));
+    /// assert!(!valord.contains_key(&"key3"));
     /// ```
-    pub fn len(&self) -> usize {
-        self.map.len() - self.free_indexs.len()
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
     }
 
-    /// Re-order the ValordMap by value.ord_by().
+    /// Returns the number of entries whose value.ord_by() equals `target`.
     ///
     /// # Example
     ///
     /// ```
-    /// use std::cell::Cell;
     /// use valord_map::ValordMap;
     ///
     /// let mut valord = ValordMap::new();
-    /// valord.insert("qians", Cell::new(1));
-    /// valord.insert("tedious", Cell::new(2));
-    /// valord.insert("xuandu", Cell::new(3));
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 2);
+    /// valord.insert("xuandu", 2);
     ///
-    /// valord
-    ///     .iter()
-    ///     .enumerate()
-    ///     .for_each(|(i, (_, v))| v.set(5 - i));
+    /// assert_eq!(valord.count_at(&2), 2);
+    /// assert_eq!(valord.count_at(&3), 0);
+    /// ```
+    pub fn count_at(&self, target: &T) -> usize {
+        self.sorted_indexs.get(target).map_or(0, HashSet::len)
+    }
+
+    /// Returns an iterator over the entries whose value.ord_by() equals `target`.
     ///
-    /// assert_eq!(
-    ///     valord.iter().collect::<Vec<_>>(),
-    ///     vec![
-    ///         (&"qians", &Cell::new(5)),
-    ///         (&"tedious", &Cell::new(4)),
-    ///         (&"xuandu", &Cell::new(3))
-    ///     ]
-    /// );
+    /// # Example
     ///
-    /// valord.re_order();
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 2);
+    /// valord.insert("xuandu", 2);
+    ///
+    /// let mut ties: Vec<_> = valord.entries_at(&2).map(|(k, _)| *k).collect();
+    /// ties.sort();
+    /// assert_eq!(ties, vec!["tedious", "xuandu"]);
+    /// assert!(valord.entries_at(&3).next().is_none());
+    /// ```
+    pub fn entries_at(&self, target: &T) -> impl Iterator<Item = (&K, &V)> {
+        self.sorted_indexs
+            .get(target)
+            .into_iter()
+            .flat_map(|indexs| self.iter_from_indexs(indexs))
+    }
+
+    /// Returns the keys of the entries whose value.ord_by() equals `target` and
+    /// whose key falls in `key_range`, in ascending key order.
+    ///
+    /// Buckets are hash sets, so the whole bucket is scanned and the matching keys
+    /// are sorted on every call.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("d", 1);
+    /// valord.insert("a", 1);
+    /// valord.insert("c", 1);
+    /// valord.insert("b", 2);
+    ///
+    /// let keys: Vec<_> = valord.keys_in_bucket_range(&1, "b"..).collect();
+    /// assert_eq!(keys, vec![&"c", &"d"]);
+    /// ```
+    pub fn keys_in_bucket_range<R>(&self, target: &T, key_range: R) -> impl Iterator<Item = &K>
+    where
+        K: Ord,
+        R: std::ops::RangeBounds<K>,
+    {
+        let mut keys: Vec<_> = self
+            .entries_at(target)
+            .map(|(k, _)| k)
+            .filter(|k| key_range.contains(k))
+            .collect();
+        keys.sort_unstable();
+        keys.into_iter()
+    }
+
+    /// Returns every `ord_by()` tier front to back, each with the entries in it.
+    ///
+    /// The order of entries within a tier is unspecified.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 2);
+    /// valord.insert("tedious", 1);
+    /// valord.insert("xuandu", 2);
+    /// valord.insert("sheng", 3);
+    ///
+    /// let mut tiers = valord.to_bucket_deque();
+    /// assert_eq!(tiers.len(), 3);
+    /// assert_eq!(tiers.pop_front(), Some((1, vec![(&"tedious", &1)])));
+    /// assert_eq!(tiers.pop_back(), Some((3, vec![(&"sheng", &3)])));
+    ///
+    /// let (target, mut tier) = tiers.pop_front().unwrap();
+    /// tier.sort();
+    /// assert_eq!(target, 2);
+    /// assert_eq!(tier, vec![(&"qians", &2), (&"xuandu", &2)]);
+    /// ```
+    pub fn to_bucket_deque(&self) -> VecDeque<(T, Vec<(&K, &V)>)> {
+        self.sorted_indexs
+            .iter()
+            .map(|(target, indexs)| (target.clone(), self.iter_from_indexs(indexs).collect()))
+            .collect()
+    }
+
+    /// Returns how many `ord_by()` buckets there are of each size, keyed by the
+    /// bucket size. Useful to spot large groups of ties.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("a", 1);
+    /// valord.insert("b", 2);
+    /// valord.insert("c", 2);
+    /// valord.insert("d", 3);
+    /// valord.insert("e", 3);
+    /// valord.insert("f", 3);
+    /// valord.insert("g", 4);
     ///
     /// assert_eq!(
-    ///     valord.iter().collect::<Vec<_>>(),
-    ///     vec![
-    ///         (&"xuandu", &Cell::new(3)),
-    ///         (&"tedious", &Cell::new(4)),
-    ///         (&"qians", &Cell::new(5)),
-    ///     ]
+    ///     valord.bucket_size_histogram(),
+    ///     BTreeMap::from([(1, 2), (2, 1), (3, 1)])
     /// );
     /// ```
-    pub fn re_order(&mut self) {
-        let mut sorted = BTreeMap::<T, HashSet<usize>>::new();
-        self.map
+    pub fn bucket_size_histogram(&self) -> BTreeMap<usize, usize> {
+        self.sorted_indexs
+            .values()
+            .fold(BTreeMap::new(), |mut histogram, indexs| {
+                *histogram.entry(indexs.len()).or_default() += 1;
+                histogram
+            })
+    }
+
+    /// Get the ref value by given key, or return `None` if not found
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("key1", 1);
+    /// valord.insert("key2", 2);
+    /// valord.insert("key3", 3);
+    ///
+    /// let mut val1 = valord.get(&"key2");
+    /// let mut val2 = valord.get(&"key4");
+    /// assert_eq!(val1.unwrap(), &2);
+    /// assert_eq!(val2, None);
+    /// ```
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.map.get(key).and_then(|v| v.as_ref())
+    }
+
+    /// Get the ref mut value by given key, or return `None` if not found
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("key1", 1);
+    /// valord.insert("key2", 2);
+    /// valord.insert("key3", 3);
+    ///
+    /// let mut val = valord.get_mut(&"key2").unwrap();
+    /// *val = 4;
+    /// drop(val);
+    /// assert_eq!(valord.get(&"key2").unwrap(), &4);
+    /// assert_eq!(valord.last(), vec![(&"key2", &4)]);
+    /// ```
+    pub fn get_mut<'a>(&'a mut self, key: &K) -> Option<RawEntry<'a, T, K, V, O>> {
+        RawEntry::try_new_by_key(self, key)
+    }
+
+    /// Get the ref mut values of several keys at once, or return `None` if any key
+    /// is not found or two keys refer to the same entry.
+    ///
+    /// Each entry is re-sorted when it is dropped, like [`get_mut`](ValordMap::get_mut).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 2);
+    /// valord.insert("xuandu", 3);
+    ///
+    /// let [mut a, mut b] = valord.get_disjoint_mut([&"qians", &"xuandu"]).unwrap();
+    /// std::mem::swap(&mut *a, &mut *b);
+    /// drop(a);
+    /// drop(b);
+    ///
+    /// assert_eq!(valord.first(), vec![(&"xuandu", &1)]);
+    /// assert_eq!(valord.last(), vec![(&"qians", &3)]);
+    ///
+    /// assert!(valord.get_disjoint_mut([&"qians", &"qians"]).is_none());
+    /// assert!(valord.get_disjoint_mut([&"qians", &"sheng"]).is_none());
+    /// ```
+    pub fn get_disjoint_mut<const N: usize>(
+        &mut self,
+        keys: [&K; N],
+    ) -> Option<[RawEntry<'_, T, K, V, O>; N]> {
+        let mut indexs = [0; N];
+        for (i, key) in keys.into_iter().enumerate() {
+            let (index, _, v) = self.map.get_full(key)?;
+            v.as_ref()?;
+            if indexs[..i].contains(&index) {
+                return None;
+            }
+            indexs[i] = index;
+        }
+
+        RawEntry::from_indexs(self, indexs).try_into().ok()
+    }
+
+    /// Get the ref mut values of two keys at once, or return `None` if either key
+    /// is not found or both keys refer to the same entry.
+    ///
+    /// This is [`get_disjoint_mut`](ValordMap::get_disjoint_mut) for the common two-key case.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 10);
+    /// valord.insert("tedious", 20);
+    ///
+    /// let (mut from, mut to) = valord.get_mut_pair(&"tedious", &"qians").unwrap();
+    /// *from -= 15;
+    /// *to += 15;
+    /// drop((from, to));
+    ///
+    /// assert_eq!(valord.first(), vec![(&"tedious", &5)]);
+    /// assert_eq!(valord.last(), vec![(&"qians", &25)]);
+    /// assert!(valord.get_mut_pair(&"qians", &"qians").is_none());
+    /// ```
+    pub fn get_mut_pair(&mut self, a: &K, b: &K) -> Option<RawEntryPair<'_, T, K, V, O>> {
+        let [a, b] = self.get_disjoint_mut([a, b])?;
+        Some((a, b))
+    }
+
+    /// Modify value in map, if exist return true, else return false
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 1);
+    ///
+    /// assert!(valord.modify(&"qians", |v| *v = 2));
+    /// assert_eq!(valord.iter().next().unwrap(), (&"qians", &2));
+    /// ```
+    pub fn modify<F>(&mut self, key: &K, op: F) -> bool
+    where
+        F: Fn(&mut V),
+    {
+        if let Some((index, _, v)) = Self::get_full_mut(&mut self.map, key) {
+            Self::remove_from_indexs(
+                &mut self.sorted_indexs,
+                &mut self.prewarmed,
+                &self.ord_by.ord_by(v),
+                index,
+            );
+            op(v);
+            Self::bucket_mut(
+                &mut self.sorted_indexs,
+                &mut self.prewarmed,
+                self.ord_by.ord_by(v),
+            )
+            .insert(index);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Modify the value of `key` like [`modify`](ValordMap::modify), returning the
+    /// entry's rank after re-sorting, or `None` if the key is not in the map.
+    ///
+    /// The rank is the number of entries with a strictly smaller value.ord_by(), as
+    /// in [`iter_ranked`](ValordMap::iter_ranked).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 2);
+    /// valord.insert("xuandu", 3);
+    ///
+    /// assert_eq!(valord.modify_and_rank(&"qians", |v| *v = 5), Some(2));
+    /// assert_eq!(valord.modify_and_rank(&"xuandu", |v| *v = 2), Some(0));
+    /// assert_eq!(valord.modify_and_rank(&"sheng", |v| *v = 0), None);
+    /// ```
+    pub fn modify_and_rank<F>(&mut self, key: &K, op: F) -> Option<usize>
+    where
+        F: FnOnce(&mut V),
+    {
+        let (index, _, v) = Self::get_full_mut(&mut self.map, key)?;
+        Self::remove_from_indexs(
+            &mut self.sorted_indexs,
+            &mut self.prewarmed,
+            &self.ord_by.ord_by(v),
+            index,
+        );
+        op(v);
+        let ord_by = self.ord_by.ord_by(v);
+        let rank = self.range_count(..&ord_by);
+        Self::bucket_mut(&mut self.sorted_indexs, &mut self.prewarmed, ord_by).insert(index);
+        Some(rank)
+    }
+
+    /// Replace the value of an existing key, returning the old value.
+    ///
+    /// The sorted buckets are only touched if the new value.ord_by() differs from
+    /// the old one, so updates that keep their ordering value are cheap. If `key`
+    /// is not in the map, nothing is inserted and `None` is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::{OrdBy, ValordMap};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// struct Player {
+    ///     name: &'static str,
+    ///     score: u32,
+    /// }
+    ///
+    /// impl OrdBy for Player {
+    ///     type Target = u32;
+    ///     fn ord_by(&self) -> u32 {
+    ///         self.score
+    ///     }
+    /// }
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert(1, Player { name: "qians", score: 3 });
+    /// valord.insert(2, Player { name: "tedious", score: 5 });
+    ///
+    /// let old = valord.update_value(&1, Player { name: "qians2", score: 3 });
+    /// assert_eq!(old, Some(Player { name: "qians", score: 3 }));
+    ///
+    /// valord.update_value(&1, Player { name: "qians3", score: 7 });
+    /// assert_eq!(valord.last(), vec![(&1, &Player { name: "qians3", score: 7 })]);
+    ///
+    /// assert_eq!(valord.update_value(&3, Player { name: "xuandu", score: 1 }), None);
+    /// assert_eq!(valord.len(), 2);
+    /// ```
+    pub fn update_value(&mut self, key: &K, value: V) -> Option<V> {
+        let (index, _, old_val) = Self::get_full_mut(&mut self.map, key)?;
+        let old_ord_by = self.ord_by.ord_by(old_val);
+        let new_ord_by = self.ord_by.ord_by(&value);
+        if old_ord_by != new_ord_by {
+            Self::remove_from_indexs(
+                &mut self.sorted_indexs,
+                &mut self.prewarmed,
+                &old_ord_by,
+                index,
+            );
+            Self::bucket_mut(&mut self.sorted_indexs, &mut self.prewarmed, new_ord_by)
+                .insert(index);
+        }
+        Some(std::mem::replace(old_val, value))
+    }
+
+    /// remove from ValordMap
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert(1, "a");
+    /// valord.insert(2, "b");
+    ///
+    /// let removed_value = valord.remove(&1);
+    /// assert_eq!(removed_value, Some("a"));
+    /// assert_eq!(valord.get(&1), None);
+    /// ```
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.remove_entry(key).map(|v| v.1)
+    }
+
+    /// Removes a key from the map, returning the stored key and value if the
+    /// key was previously in the map.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert(1, "a");
+    /// valord.insert(2, "b");
+    ///
+    /// let removed_entry = valord.remove_entry(&1);
+    /// assert_eq!(removed_entry, Some((&1, "a")));
+    /// assert_eq!(valord.get(&1), None);
+    /// ```
+    pub fn remove_entry<'a>(&'a mut self, key: &'a K) -> Option<(&'a K, V)> {
+        if let Some((i, k, v)) = self.map.get_full_mut(key) {
+            if let Some(old) = v.take() {
+                self.free_indexs.insert(i);
+                Self::remove_from_indexs(
+                    &mut self.sorted_indexs,
+                    &mut self.prewarmed,
+                    &self.ord_by.ord_by(&old),
+                    i,
+                );
+                return Some((k, old));
+            };
+        }
+        None
+    }
+
+    /// Removes an entry with the smallest value.ord_by(), returning the internal
+    /// index it occupied along with its key and value.
+    ///
+    /// The slot keeps its key until a later insert reuses it, which is why the key
+    /// is cloned out. Among tied entries, which one is removed is unspecified.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 2);
+    /// valord.insert("tedious", 1);
+    /// valord.insert("xuandu", 3);
+    ///
+    /// assert_eq!(valord.remove_min(), Some((1, "tedious", 1)));
+    /// assert_eq!(valord.remove_min(), Some((0, "qians", 2)));
+    /// assert_eq!(valord.len(), 1);
+    /// ```
+    pub fn remove_min(&mut self) -> Option<(usize, K, V)>
+    where
+        K: Clone,
+    {
+        let index = *self.sorted_indexs.first_key_value()?.1.iter().next()?;
+        self.remove_full_by_index(index)
+    }
+
+    /// Removes an entry with the largest value.ord_by(), returning the internal
+    /// index it occupied along with its key and value.
+    ///
+    /// The slot keeps its key until a later insert reuses it, which is why the key
+    /// is cloned out. Among tied entries, which one is removed is unspecified.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 2);
+    /// valord.insert("tedious", 1);
+    /// valord.insert("xuandu", 3);
+    ///
+    /// assert_eq!(valord.remove_max(), Some((2, "xuandu", 3)));
+    /// assert_eq!(valord.last(), vec![(&"qians", &2)]);
+    /// ```
+    pub fn remove_max(&mut self) -> Option<(usize, K, V)>
+    where
+        K: Clone,
+    {
+        let index = *self.sorted_indexs.last_key_value()?.1.iter().next()?;
+        self.remove_full_by_index(index)
+    }
+
+    /// Repeatedly removes an entry with the smallest value.ord_by() while `pred`
+    /// holds for it, returning the removed pairs order by value.ord_by().
+    ///
+    /// Stops at the first entry `pred` rejects, which stays in the map. Like
+    /// [`remove_min`](ValordMap::remove_min), tied entries are visited in an
+    /// unspecified order and keys are cloned out.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 30);
+    /// valord.insert("tedious", 10);
+    /// valord.insert("xuandu", 20);
+    ///
+    /// let overdue = valord.drain_min_while(|_, due| *due < 25);
+    /// assert_eq!(overdue, vec![("tedious", 10), ("xuandu", 20)]);
+    /// assert_eq!(valord.first(), vec![(&"qians", &30)]);
+    /// ```
+    pub fn drain_min_while<F>(&mut self, mut pred: F) -> Vec<(K, V)>
+    where
+        K: Clone,
+        F: FnMut(&K, &V) -> bool,
+    {
+        let mut drained = Vec::new();
+        while let Some(index) = self
+            .sorted_indexs
+            .first_key_value()
+            .and_then(|(_, indexs)| indexs.iter().next().copied())
+        {
+            match self.get_by_index(index) {
+                Some((k, v)) if pred(k, v) => {}
+                _ => break,
+            }
+            if let Some((_, k, v)) = self.remove_full_by_index(index) {
+                drained.push((k, v));
+            }
+        }
+        drained
+    }
+
+    /// Removes all but the `k` entries with the largest value.ord_by(), returning
+    /// the removed pairs order by value.ord_by().
+    ///
+    /// Removed slots are kept for their keys as usual, so the map's capacity is
+    /// unchanged. If the cut falls between tied entries, which of them are kept is
+    /// unspecified.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 4);
+    /// valord.insert("xuandu", 2);
+    /// valord.insert("sheng", 3);
+    ///
+    /// let evicted = valord.drain_all_but_top_k(2);
+    /// assert_eq!(evicted, vec![("qians", 1), ("xuandu", 2)]);
+    /// assert_eq!(valord.keys().collect::<Vec<_>>(), vec![&"sheng", &"tedious"]);
+    /// ```
+    pub fn drain_all_but_top_k(&mut self, k: usize) -> Vec<(K, V)>
+    where
+        K: Clone,
+    {
+        let mut evict = self.len().saturating_sub(k);
+        self.drain_min_while(|_, _| {
+            let more = evict > 0;
+            evict = evict.saturating_sub(1);
+            more
+        })
+    }
+
+    /// Removes every entry whose key is not in `keep`.
+    ///
+    /// This is [`retain_stable`](ValordMap::retain_stable) with a membership test, so
+    /// surviving entries keep their internal indexes. It walks the whole map once,
+    /// however small `keep` is, since every entry outside the set has to be freed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::HashSet;
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 2);
+    /// valord.insert("xuandu", 3);
+    ///
+    /// valord.retain_in_set(&HashSet::from(["xuandu", "qians", "sheng"]));
+    ///
+    /// assert_eq!(valord.len(), 2);
+    /// assert_eq!(valord.keys().collect::<Vec<_>>(), vec![&"qians", &"xuandu"]);
+    /// ```
+    pub fn retain_in_set(&mut self, keep: &HashSet<K>) {
+        self.retain_stable(|k, _| keep.contains(k));
+    }
+
+    /// Keeps only the entries for which `f` returns `true`, without moving any of
+    /// the survivors: each keeps the internal index it had.
+    ///
+    /// Removed slots are freed like with [`remove`](ValordMap::remove): a later insert
+    /// may reuse them, but no surviving entry is ever moved into one. Use
+    /// [`retain_compact`](ValordMap::retain_compact) to drop the freed slots right
+    /// away, at the cost of renumbering.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 2);
+    /// valord.insert("xuandu", 3);
+    ///
+    /// valord.retain_stable(|_, v| *v != 2);
+    ///
+    /// assert_eq!(valord.len(), 2);
+    /// assert_eq!(valord.keys().collect::<Vec<_>>(), vec![&"qians", &"xuandu"]);
+    /// ```
+    pub fn retain_stable<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &V) -> bool,
+    {
+        for index in 0..self.map.len() {
+            if matches!(self.map.get_index(index), Some((k, Some(v))) if !f(k, v)) {
+                self.remove_by_index(index);
+            }
+        }
+    }
+
+    /// Return the number of key-value pairs in the map.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert(1, "a");
+    /// valord.insert(2, "b");
+    /// valord.insert(3, "c");
+    /// valord.insert(2, "d");
+    ///
+    /// assert_eq!(valord.len(), 3);
+    ///
+    /// let removed_value = valord.remove(&1);
+    /// assert_eq!(removed_value, Some("a"));
+    /// assert_eq!(valord.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.map.len() - self.free_indexs.len()
+    }
+
+    /// Re-order the ValordMap by value.ord_by().
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::cell::Cell;
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", Cell::new(1));
+    /// valord.insert("tedious", Cell::new(2));
+    /// valord.insert("xuandu", Cell::new(3));
+    ///
+    /// valord
+    ///     .iter()
+    ///     .enumerate()
+    ///     .for_each(|(i, (_, v))| v.set(5 - i));
+    ///
+    /// assert_eq!(
+    ///     valord.iter().collect::<Vec<_>>(),
+    ///     vec![
+    ///         (&"qians", &Cell::new(5)),
+    ///         (&"tedious", &Cell::new(4)),
+    ///         (&"xuandu", &Cell::new(3))
+    ///     ]
+    /// );
+    ///
+    /// valord.re_order();
+    ///
+    /// assert_eq!(
+    ///     valord.iter().collect::<Vec<_>>(),
+    ///     vec![
+    ///         (&"xuandu", &Cell::new(3)),
+    ///         (&"tedious", &Cell::new(4)),
+    ///         (&"qians", &Cell::new(5)),
+    ///     ]
+    /// );
+    /// ```
+    pub fn re_order(&mut self) {
+        self.clear_buckets();
+        for (i, (_, v)) in self.map.iter().enumerate() {
+            if let Some(v) = v {
+                let ord_by = self.ord_by.ord_by(v);
+                Self::bucket_mut(&mut self.sorted_indexs, &mut self.prewarmed, ord_by).insert(i);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of entries for which `pred` returns `true`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 10);
+    /// valord.insert("tedious", 25);
+    /// valord.insert("xuandu", 40);
+    /// valord.remove(&"xuandu");
+    ///
+    /// assert_eq!(valord.count_where(|_, v| *v > 20), 1);
+    /// assert_eq!(valord.count_where(|k, _| k.starts_with('q')), 1);
+    /// ```
+    pub fn count_where<F>(&self, pred: F) -> usize
+    where
+        F: Fn(&K, &V) -> bool,
+    {
+        self.map
+            .iter()
+            .filter(|(k, v)| v.as_ref().is_some_and(|v| pred(k, v)))
+            .count()
+    }
+
+    /// Returns a fingerprint of the sorted state: the sequence of `ord_by()` values
+    /// together with the keys sharing each of them.
+    ///
+    /// Entries sharing an `ord_by()` value have no defined order between them, so
+    /// their keys are combined order-independently. Two maps with the same logical
+    /// sorted state get the same fingerprint, whatever order they were built in.
+    /// The value is only meant to be compared within the same build of the program.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut a = ValordMap::new();
+    /// a.insert("qians", 1);
+    /// a.insert("tedious", 2);
+    /// a.insert("xuandu", 2);
+    ///
+    /// let mut b = ValordMap::new();
+    /// b.insert("xuandu", 2);
+    /// b.insert("tedious", 2);
+    /// b.insert("qians", 1);
+    ///
+    /// assert_eq!(a.ordering_fingerprint(), b.ordering_fingerprint());
+    ///
+    /// b.insert("qians", 3);
+    /// assert_ne!(a.ordering_fingerprint(), b.ordering_fingerprint());
+    /// ```
+    pub fn ordering_fingerprint(&self) -> u64
+    where
+        T: Hash,
+    {
+        let mut hasher = DefaultHasher::new();
+        self.sorted_indexs.iter().for_each(|(t, indexs)| {
+            t.hash(&mut hasher);
+            indexs.len().hash(&mut hasher);
+            self.iter_from_indexs(indexs)
+                .map(|(k, _)| {
+                    let mut key_hasher = DefaultHasher::new();
+                    k.hash(&mut key_hasher);
+                    key_hasher.finish()
+                })
+                .fold(0u64, u64::wrapping_add)
+                .hash(&mut hasher);
+        });
+        hasher.finish()
+    }
+
+    /// Drops the slots left behind by removed entries and releases unused capacity.
+    ///
+    /// Entries are re-indexed contiguously and the sorted order is rebuilt like
+    /// [`re_order`](ValordMap::re_order) does.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// (0..10).for_each(|i| valord.insert(i, 10 - i));
+    /// (0..5).for_each(|i| {
+    ///     valord.remove(&i);
+    /// });
+    ///
+    /// valord.shrink_to_fit();
+    ///
+    /// assert_eq!(valord.len(), 5);
+    /// assert_eq!(valord.keys().collect::<Vec<_>>(), vec![&9, &8, &7, &6, &5]);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.map.retain(|_, v| v.is_some());
+        self.map.shrink_to_fit();
+        self.free_indexs.clear();
+        self.free_indexs.shrink_to_fit();
+        self.re_order();
+    }
+
+    /// Keeps only the entries for which `f` returns `true`, then compacts the map
+    /// like [`shrink_to_fit`](ValordMap::shrink_to_fit) does.
+    ///
+    /// Returns where every kept entry moved, as a map from its old internal index
+    /// to its new one. Surviving entries keep their relative slot order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 2);
+    /// valord.insert("xuandu", 3);
+    ///
+    /// let moved = valord.retain_compact(|_, v| *v != 2);
+    ///
+    /// assert_eq!(moved.get(&0), Some(&0));
+    /// assert_eq!(moved.get(&2), Some(&1));
+    /// assert_eq!(moved.len(), 2);
+    /// assert_eq!(valord.keys().collect::<Vec<_>>(), vec![&"qians", &"xuandu"]);
+    /// ```
+    pub fn retain_compact<F>(&mut self, mut f: F) -> HashMap<usize, usize>
+    where
+        F: FnMut(&K, &V) -> bool,
+    {
+        let mut moved = HashMap::new();
+        let mut old_index = 0;
+        self.map.retain(|k, v| {
+            let keep = v.as_ref().is_some_and(|v| f(k, v));
+            if keep {
+                moved.insert(old_index, moved.len());
+            }
+            old_index += 1;
+            keep
+        });
+        self.map.shrink_to_fit();
+        self.free_indexs.clear();
+        self.free_indexs.shrink_to_fit();
+        self.re_order();
+        moved
+    }
+
+    /// Keeps a bucket allocation for each of the given ordering values, so the sets
+    /// behind those buckets are reused instead of reallocated.
+    ///
+    /// The first insert with a prewarmed value takes over the reserved set without
+    /// allocating it. When the bucket later empties, its set is kept, along with
+    /// whatever capacity it grew to, rather than being freed. This helps tiers that
+    /// repeatedly fill up and drain. The sorted index itself still only holds
+    /// non-empty buckets, so nothing else is affected, and inserting the first entry
+    /// of a tier still adds a node to the sorted index; see `benches/prewarm.rs`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.prewarm_buckets(0..100);
+    /// assert!(valord.first().is_empty());
+    ///
+    /// valord.insert("qians", 42);
+    /// valord.insert("tedious", 7);
+    /// assert_eq!(valord.first(), vec![(&"tedious", &7)]);
+    /// assert_eq!(valord.last(), vec![(&"qians", &42)]);
+    /// ```
+    pub fn prewarm_buckets(&mut self, ords: impl IntoIterator<Item = T>) {
+        ords.into_iter().for_each(|ord_by| {
+            if self.sorted_indexs.contains_key(&ord_by) {
+                self.prewarmed.entry(ord_by).or_default();
+            } else {
+                self.prewarmed
+                    .entry(ord_by)
+                    .or_insert_with(|| HashSet::with_capacity(1));
+            }
+        });
+    }
+
+    /// Consumes the ValordMap and splits it into the `n` smallest entries and the rest,
+    /// order by value.ord_by(). Both halves are freshly indexed.
+    ///
+    /// If the split point falls between entries sharing the same `ord_by()` value,
+    /// which of them end up in the first half is unspecified.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 2);
+    /// valord.insert("xuandu", 3);
+    ///
+    /// let (smallest, rest) = valord.into_split_at_rank(2);
+    /// assert_eq!(smallest.keys().collect::<Vec<_>>(), vec![&"qians", &"tedious"]);
+    /// assert_eq!(rest.keys().collect::<Vec<_>>(), vec![&"xuandu"]);
+    /// ```
+    pub fn into_split_at_rank(self, n: usize) -> (Self, Self)
+    where
+        O: Clone,
+    {
+        let mut smallest = Self::with_ord_by(self.ord_by.clone());
+        let mut rest = Self::with_ord_by(self.ord_by.clone());
+        self.into_sorted().enumerate().for_each(|(rank, (k, v))| {
+            if rank < n {
+                smallest.insert(k, v)
+            } else {
+                rest.insert(k, v)
+            }
+        });
+        (smallest, rest)
+    }
+
+    /// Consumes the ValordMap and returns a compact copy with no removed slots,
+    /// where entries are indexed `0..len` in ascending `ord_by()` order.
+    ///
+    /// The internal layout then matches the sorted order, which helps locality
+    /// when walking or serializing the map.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 3);
+    /// valord.insert("tedious", 1);
+    /// valord.insert("xuandu", 2);
+    /// valord.remove(&"qians");
+    ///
+    /// let compact = valord.compact_ordered();
+    /// assert_eq!(compact.len(), 2);
+    /// assert_eq!(compact.iter().collect::<Vec<_>>(), vec![(&"tedious", &1), (&"xuandu", &2)]);
+    /// ```
+    pub fn compact_ordered(self) -> Self
+    where
+        O: Clone,
+    {
+        let mut compact = Self::with_ord_by(self.ord_by.clone());
+        compact.map.reserve(self.len());
+        compact.extend(self.into_sorted());
+        compact
+    }
+
+    /// Returns an immutable view of the map as it is now. The map can keep
+    /// changing without affecting the snapshot.
+    ///
+    /// The map is copied once; clones of the snapshot share that copy.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 2);
+    ///
+    /// let snapshot = valord.snapshot();
+    /// valord.remove(&"qians");
+    ///
+    /// assert_eq!(snapshot.len(), 2);
+    /// assert_eq!(snapshot.first(), vec![(&"qians", &1)]);
+    /// assert_eq!(valord.first(), vec![(&"tedious", &2)]);
+    /// ```
+    pub fn snapshot(&self) -> ValordSnapshot<T, K, V, O>
+    where
+        K: Clone,
+        V: Clone,
+        O: Clone,
+    {
+        ValordSnapshot::new(self.clone())
+    }
+
+    /// Consumes the ValordMap into its `ord_by()` tiers in ascending order, each
+    /// holding the owned entries in it. Removed entries are dropped.
+    ///
+    /// The order of entries within a tier is unspecified.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 2);
+    /// valord.insert("tedious", 1);
+    /// valord.insert("xuandu", 3);
+    /// valord.remove(&"xuandu");
+    ///
+    /// assert_eq!(
+    ///     valord.into_grouped(),
+    ///     vec![(1, vec![("tedious", 1)]), (2, vec![("qians", 2)])]
+    /// );
+    /// ```
+    pub fn into_grouped(self) -> Vec<(T, Vec<(K, V)>)> {
+        let mut slots: Vec<_> = self
+            .map
+            .into_iter()
+            .map(|(k, v)| v.map(|v| (k, v)))
+            .collect();
+        self.sorted_indexs
+            .into_iter()
+            .map(|(target, indexs)| {
+                let entries = indexs
+                    .into_iter()
+                    .filter_map(|index| slots.get_mut(index)?.take())
+                    .collect();
+                (target, entries)
+            })
+            .collect()
+    }
+
+    /// Moves all entries of `other` into this ValordMap, leaving `other` empty.
+    ///
+    /// Like [`BTreeMap::append`], when a key is in both maps the entry from `other`
+    /// overwrites the one in `self`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 2);
+    ///
+    /// let mut other = ValordMap::new();
+    /// other.insert("tedious", 4);
+    /// other.insert("xuandu", 3);
+    ///
+    /// valord.append(&mut other);
+    ///
+    /// assert!(other.is_empty());
+    /// assert_eq!(valord.len(), 3);
+    /// assert_eq!(
+    ///     valord.iter().collect::<Vec<_>>(),
+    ///     vec![(&"qians", &1), (&"xuandu", &3), (&"tedious", &4)]
+    /// );
+    /// ```
+    pub fn append(&mut self, other: &mut ValordMap<T, K, V, O>) {
+        // `other`'s indexs mean nothing in `self`, so every entry goes through `_insert`.
+        other.clear_buckets();
+        other.free_indexs.clear();
+        std::mem::take(&mut other.map)
+            .into_iter()
+            .filter_map(|(k, v)| v.map(|v| (k, v)))
+            .for_each(|(k, v)| {
+                self._insert(k, v);
+            });
+    }
+
+    /// Clones all entries of `other` into this ValordMap, leaving `other` untouched.
+    ///
+    /// Like [`append`](ValordMap::append), when a key is in both maps the entry
+    /// from `other` overwrites the one in `self`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 1);
+    /// valord.insert("tedious", 2);
+    ///
+    /// let mut other = ValordMap::new();
+    /// other.insert("tedious", 4);
+    /// other.insert("xuandu", 3);
+    ///
+    /// valord.extend_ref(&other);
+    ///
+    /// assert_eq!(other.len(), 2);
+    /// assert_eq!(
+    ///     valord.iter().collect::<Vec<_>>(),
+    ///     vec![(&"qians", &1), (&"xuandu", &3), (&"tedious", &4)]
+    /// );
+    /// ```
+    pub fn extend_ref(&mut self, other: &ValordMap<T, K, V, O>)
+    where
+        K: Clone,
+        V: Clone,
+    {
+        other.iter().for_each(|(k, v)| {
+            self._insert(k.clone(), v.clone());
+        });
+    }
+
+    /// Moves all entries of `other` into this ValordMap, combining the values of
+    /// keys present in both with `combine(self_value, other_value)`.
+    ///
+    /// Combined entries are re-sorted by the new value.ord_by().
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::ValordMap;
+    ///
+    /// let mut valord = ValordMap::new();
+    /// valord.insert("qians", 5);
+    /// valord.insert("tedious", 1);
+    ///
+    /// let mut other = ValordMap::new();
+    /// other.insert("tedious", 7);
+    /// other.insert("xuandu", 3);
+    ///
+    /// valord.merge_recompute(other, |a, b| a + b);
+    ///
+    /// assert_eq!(
+    ///     valord.iter().collect::<Vec<_>>(),
+    ///     vec![(&"xuandu", &3), (&"qians", &5), (&"tedious", &8)]
+    /// );
+    /// ```
+    pub fn merge_recompute<F>(&mut self, other: ValordMap<T, K, V, O>, combine: F)
+    where
+        F: Fn(V, V) -> V,
+    {
+        other
+            .into_iter()
+            .for_each(|(k, v)| match self.map.get_full_mut(&k) {
+                Some((index, _, slot)) if slot.is_some() => {
+                    let existing = slot.take().unwrap();
+                    Self::remove_from_indexs(
+                        &mut self.sorted_indexs,
+                        &mut self.prewarmed,
+                        &self.ord_by.ord_by(&existing),
+                        index,
+                    );
+                    let merged = combine(existing, v);
+                    Self::bucket_mut(
+                        &mut self.sorted_indexs,
+                        &mut self.prewarmed,
+                        self.ord_by.ord_by(&merged),
+                    )
+                    .insert(index);
+                    *slot = Some(merged);
+                }
+                _ => {
+                    self._insert(k, v);
+                }
+            });
+    }
+
+    fn get_by_index(&self, index: usize) -> Option<(&K, &V)> {
+        self.map
+            .get_index(index)
+            .and_then(|(k, maybe_val)| maybe_val.as_ref().map(|v| (k, v)))
+    }
+
+    fn get_full_mut<'a>(
+        map: &'a mut IndexMap<K, Option<V>>,
+        key: &'a K,
+    ) -> Option<(usize, &'a K, &'a mut V)> {
+        map.get_full_mut(key)
+            .and_then(|(i, k, v)| v.as_mut().map(|v| (i, k, v)))
+    }
+
+    fn iter_from_indexs<'a>(
+        &'a self,
+        indexs: &'a HashSet<usize>,
+    ) -> impl Iterator<Item = (&'a K, &'a V)> {
+        indexs.iter().filter_map(|index| self.get_by_index(*index))
+    }
+
+    fn into_sorted(self) -> impl Iterator<Item = (K, V)> {
+        let mut slots: Vec<_> = self
+            .map
+            .into_iter()
+            .map(|(k, v)| v.map(|v| (k, v)))
+            .collect();
+        self.sorted_indexs
+            .into_values()
+            .flatten()
+            .filter_map(move |index| slots.get_mut(index)?.take())
+    }
+
+    fn insert_to_indexs(&mut self, index: usize) {
+        if let Some(ord_by) = self.get_by_index(index).map(|(_, v)| self.ord_by.ord_by(v)) {
+            Self::bucket_mut(&mut self.sorted_indexs, &mut self.prewarmed, ord_by).insert(index);
+        };
+    }
+
+    fn adjust_bucket<F>(&mut self, (target, mut indexs): (T, HashSet<usize>), mut f: F)
+    where
+        F: FnMut(&K, &mut V),
+    {
+        let mut moved = Vec::new();
+        indexs.retain(|index| match self.map.get_index_mut(*index) {
+            Some((k, Some(v))) => {
+                f(k, v);
+                let ord_by = self.ord_by.ord_by(v);
+                if ord_by == target {
+                    return true;
+                }
+                moved.push((ord_by, *index));
+                false
+            }
+            _ => false,
+        });
+
+        if indexs.is_empty() {
+            Self::release_bucket(&mut self.prewarmed, &target, indexs);
+        } else {
+            self.sorted_indexs.insert(target, indexs);
+        }
+        moved.into_iter().for_each(|(ord_by, index)| {
+            Self::bucket_mut(&mut self.sorted_indexs, &mut self.prewarmed, ord_by).insert(index);
+        });
+    }
+
+    /// Removes every entry in `bounds`, bucket by bucket in ascending order, handing
+    /// each one to `each` with the index it occupied.
+    fn take_range<F>(&mut self, bounds: (Bound<&T>, Bound<&T>), mut each: F)
+    where
+        F: FnMut(usize, &K, V),
+    {
+        loop {
+            let Some(target) = self
+                .sorted_indexs
+                .range(bounds)
+                .next()
+                .map(|(t, _)| t.clone())
+            else {
+                break;
+            };
+            let mut indexs = self.sorted_indexs.remove(&target).unwrap_or_default();
+            indexs.drain().for_each(|index| {
+                if let Some((k, slot)) = self.map.get_index_mut(index) {
+                    if let Some(v) = slot.take() {
+                        self.free_indexs.insert(index);
+                        each(index, k, v);
+                    }
+                }
+            });
+            Self::release_bucket(&mut self.prewarmed, &target, indexs);
+        }
+    }
+
+    fn remove_by_index(&mut self, index: usize) -> Option<V> {
+        let old = self.map.get_index_mut(index)?.1.take()?;
+        self.free_indexs.insert(index);
+        Self::remove_from_indexs(
+            &mut self.sorted_indexs,
+            &mut self.prewarmed,
+            &self.ord_by.ord_by(&old),
+            index,
+        );
+        Some(old)
+    }
+
+    fn remove_full_by_index(&mut self, index: usize) -> Option<(usize, K, V)>
+    where
+        K: Clone,
+    {
+        let value = self.remove_by_index(index)?;
+        let key = self.map.get_index(index)?.0.clone();
+        Some((index, key, value))
+    }
+
+    fn remove_from_indexs(
+        sorted_indexs: &mut BTreeMap<T, HashSet<usize>>,
+        prewarmed: &mut BTreeMap<T, HashSet<usize>>,
+        key: &T,
+        index: usize,
+    ) {
+        if let Some(indexs) = sorted_indexs.get_mut(key) {
+            indexs.remove(&index);
+            if indexs.is_empty() {
+                if let Some(indexs) = sorted_indexs.remove(key) {
+                    Self::release_bucket(prewarmed, key, indexs);
+                }
+            }
+        }
+    }
+
+    /// The bucket for `ord_by`, created from its prewarmed set if it has one.
+    fn bucket_mut<'a>(
+        sorted_indexs: &'a mut BTreeMap<T, HashSet<usize>>,
+        prewarmed: &mut BTreeMap<T, HashSet<usize>>,
+        ord_by: T,
+    ) -> &'a mut HashSet<usize> {
+        match sorted_indexs.entry(ord_by) {
+            btree_map::Entry::Occupied(bucket) => bucket.into_mut(),
+            btree_map::Entry::Vacant(bucket) => {
+                let indexs = prewarmed
+                    .get_mut(bucket.key())
+                    .map(std::mem::take)
+                    .unwrap_or_default();
+                bucket.insert(indexs)
+            }
+        }
+    }
+
+    /// Hands the set of a bucket that was taken out of `sorted_indexs` back to
+    /// `prewarmed`, if its value was prewarmed.
+    fn release_bucket(
+        prewarmed: &mut BTreeMap<T, HashSet<usize>>,
+        target: &T,
+        mut indexs: HashSet<usize>,
+    ) {
+        if let Some(spare) = prewarmed.get_mut(target) {
+            indexs.clear();
+            *spare = indexs;
+        }
+    }
+
+    /// Empties `sorted_indexs`, keeping the sets of prewarmed buckets.
+    fn clear_buckets(&mut self) {
+        std::mem::take(&mut self.sorted_indexs)
+            .into_iter()
+            .for_each(|(target, indexs)| {
+                Self::release_bucket(&mut self.prewarmed, &target, indexs)
+            });
+    }
+}
+
+impl<T, K, V> Default for ValordMap<T, K, V>
+where
+    T: Ord + Clone,
+    K: std::hash::Hash + Eq,
+    V: OrdBy<Target = T>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Builds a ValordMap the same way a loop of [`insert`](ValordMap::insert) would,
+/// so later pairs overwrite earlier ones with the same key.
+///
+/// # Example
+///
+/// ```
+/// use valord_map::ValordMap;
+///
+/// let valord: ValordMap<_, _, _> = [("qians", 3), ("tedious", 1), ("qians", 2)]
+///     .into_iter()
+///     .collect();
+///
+/// assert_eq!(valord.len(), 2);
+/// assert_eq!(valord.iter().collect::<Vec<_>>(), vec![(&"tedious", &1), (&"qians", &2)]);
+/// ```
+impl<T, K, V> FromIterator<(K, V)> for ValordMap<T, K, V>
+where
+    T: Ord + Clone,
+    K: Hash + Eq,
+    V: OrdBy<Target = T>,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut valord = Self::new();
+        valord.map.reserve(iter.size_hint().0);
+        valord.extend(iter);
+        valord
+    }
+}
+
+/// Inserts every pair with [`insert`](ValordMap::insert), so later pairs overwrite
+/// earlier ones with the same key.
+///
+/// # Example
+///
+/// ```
+/// use valord_map::ValordMap;
+///
+/// let mut valord = ValordMap::new();
+/// valord.insert("qians", 3);
+/// valord.extend([("tedious", 1), ("qians", 0)]);
+///
+/// assert_eq!(valord.len(), 2);
+/// assert_eq!(valord.first(), vec![(&"qians", &0)]);
+/// ```
+impl<T, K, V, O> Extend<(K, V)> for ValordMap<T, K, V, O>
+where
+    T: Ord + Clone,
+    K: Hash + Eq,
+    O: OrdByFn<V, T>,
+{
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|(k, v)| self.insert(k, v));
+    }
+}
+
+/// Consumes the ValordMap into its key-value pairs, order by value.ord_by().
+///
+/// # Example
+///
+/// ```
+/// use valord_map::ValordMap;
+///
+/// let mut valord = ValordMap::new();
+/// valord.insert("qians", 3);
+/// valord.insert("tedious", 1);
+/// valord.insert("xuandu", 2);
+///
+/// let sorted: Vec<_> = valord.into_iter().collect();
+/// assert_eq!(sorted, vec![("tedious", 1), ("xuandu", 2), ("qians", 3)]);
+/// ```
+impl<T, K, V, O> IntoIterator for ValordMap<T, K, V, O>
+where
+    T: Ord + Clone,
+    K: Hash + Eq,
+    O: OrdByFn<V, T>,
+{
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter::new(self.into_sorted().collect())
+    }
+}
+
+/// Clones the map, including its removed slots, so internal indexes stay the same.
+///
+/// # Example
+///
+/// ```
+/// use valord_map::ValordMap;
+///
+/// let mut valord = ValordMap::new();
+/// valord.insert("qians", 1);
+/// valord.insert("tedious", 2);
+///
+/// let mut cloned = valord.clone();
+/// cloned.insert("qians", 3);
+///
+/// assert_eq!(valord.first(), vec![(&"qians", &1)]);
+/// assert_eq!(cloned.first(), vec![(&"tedious", &2)]);
+/// ```
+impl<T, K, V, O> Clone for ValordMap<T, K, V, O>
+where
+    T: Ord + Clone,
+    K: Hash + Eq + Clone,
+    V: Clone,
+    O: Clone,
+{
+    fn clone(&self) -> Self {
+        ValordMap {
+            map: self.map.clone(),
+            sorted_indexs: self.sorted_indexs.clone(),
+            free_indexs: self.free_indexs.clone(),
+            prewarmed: self.prewarmed.clone(),
+            ord_by: self.ord_by.clone(),
+        }
+    }
+}
+
+/// Formats the live key-value pairs in sorted order, hiding removed slots.
+///
+/// # Example
+///
+/// ```
+/// use valord_map::ValordMap;
+///
+/// let mut valord = ValordMap::new();
+/// valord.insert("qians", 2);
+/// valord.insert("tedious", 1);
+///
+/// assert_eq!(format!("{valord:?}"), r#"{"tedious": 1, "qians": 2}"#);
+/// ```
+impl<T, K, V, O> std::fmt::Debug for ValordMap<T, K, V, O>
+where
+    T: Ord + Clone,
+    K: Hash + Eq + std::fmt::Debug,
+    V: std::fmt::Debug,
+    O: OrdByFn<V, T>,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Two maps are equal when they hold the same key-value pairs in the same
+/// `ord_by()` buckets, no matter how their internal slots are laid out.
+///
+/// # Example
+///
+/// ```
+/// use valord_map::ValordMap;
+///
+/// let mut a = ValordMap::new();
+/// a.insert("qians", 1);
+/// a.insert("tedious", 2);
+/// a.insert("xuandu", 3);
+/// a.remove(&"tedious");
+///
+/// let mut b = ValordMap::new();
+/// b.insert("xuandu", 3);
+/// b.insert("qians", 1);
+///
+/// assert_eq!(a, b);
+/// ```
+impl<T, K, V, O> PartialEq for ValordMap<T, K, V, O>
+where
+    T: Ord + Clone,
+    K: Hash + Eq,
+    O: OrdByFn<V, T>,
+    V: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self.sorted_indexs.len() == other.sorted_indexs.len()
+            && self
+                .sorted_indexs
+                .iter()
+                .zip(other.sorted_indexs.iter())
+                .all(|((t, indexs), (other_t, other_indexs))| {
+                    t == other_t
+                        && indexs.len() == other_indexs.len()
+                        && self.iter_from_indexs(indexs).all(|(k, v)| {
+                            other.map.get_full(k).is_some_and(|(i, _, other_v)| {
+                                other_indexs.contains(&i) && other_v.as_ref() == Some(v)
+                            })
+                        })
+                })
+    }
+}
+
+impl<T, K, V, O> Eq for ValordMap<T, K, V, O>
+where
+    T: Ord + Clone,
+    K: Hash + Eq,
+    O: OrdByFn<V, T>,
+    V: Eq,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, cmp::Reverse};
+
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct OrdByValue {
+        sth: usize,
+        order_by: usize,
+    }
+
+    impl OrdByValue {
+        fn new(sth: usize, order_by: usize) -> Self {
+            Self { sth, order_by }
+        }
+    }
+
+    impl OrdBy for OrdByValue {
+        type Target = usize;
+
+        fn ord_by(&self) -> Self::Target {
+            self.order_by
+        }
+    }
+
+    #[test]
+    fn test_valord_insert_order_by() {
+        let mut valord = ValordMap::new();
+        valord.insert("qians", OrdByValue::new(123, 1));
+        valord.insert("tedious", OrdByValue::new(412, 2));
+        valord.insert("xuandu", OrdByValue::new(125, 3));
+        valord.insert("xuandu", OrdByValue::new(938, 1));
+
+        let sorted_pairs: Vec<_> = valord.iter().collect();
+
+        assert_eq!(sorted_pairs.len(), 3);
+        assert_eq!(sorted_pairs[0].1.order_by, 1);
+        assert_eq!(sorted_pairs[1].1.order_by, 1);
+        assert_eq!(sorted_pairs[2], (&"tedious", &OrdByValue::new(412, 2)));
+    }
+
+    #[test]
+    fn test_valord_remove_non_existent() {
+        let mut valord = ValordMap::new();
+        valord.insert(1, "a");
+        valord.insert(2, "b");
+
+        let removed_value = valord.remove(&3);
+        assert_eq!(removed_value, None);
+        assert_eq!(valord.get(&3), None);
+    }
+
+    #[test]
+    fn test_valord_multiple_insert_and_remove() {
+        let mut valord = ValordMap::new();
+        valord.insert("qians", 1);
+        valord.insert("tedious", 2);
+        valord.insert("xuandu", 3);
+
+        assert_eq!(valord.remove(&"tedious"), Some(2));
+        assert_eq!(valord.remove(&"qians"), Some(1));
+
+        valord.insert("x", 2);
+        valord.insert("y", 4);
+
+        let sorted_pairs: Vec<_> = valord.iter().collect();
+        println!("sorted_pairs: {sorted_pairs:?}");
+        assert_eq!(sorted_pairs.len(), 3);
+        assert_eq!(sorted_pairs[0], (&"x", &2));
+        assert_eq!(sorted_pairs[1], (&"xuandu", &3));
+        assert_eq!(sorted_pairs[2], (&"y", &4));
+    }
+
+    #[test]
+    fn test_valord_eq_ignores_layout() {
+        let mut with_holes = ValordMap::new();
+        with_holes.insert("qians", 1);
+        with_holes.insert("tedious", 2);
+        with_holes.insert("xuandu", 3);
+        with_holes.insert("sheng", 4);
+        with_holes.remove(&"tedious");
+        with_holes.remove(&"sheng");
+        with_holes.insert("xuandu", 1);
+
+        let mut fresh = ValordMap::new();
+        fresh.insert("xuandu", 1);
+        fresh.insert("qians", 1);
+
+        assert_eq!(with_holes, fresh);
+        assert_eq!(format!("{with_holes:?}").len(), format!("{fresh:?}").len());
+
+        fresh.insert("qians", 2);
+        assert_ne!(with_holes, fresh);
+
+        let mut value_differs = ValordMap::new();
+        value_differs.insert("xuandu", OrdByValue::new(1, 1));
+        value_differs.insert("qians", OrdByValue::new(2, 1));
+
+        let mut same_order = ValordMap::new();
+        same_order.insert("xuandu", OrdByValue::new(1, 1));
+        same_order.insert("qians", OrdByValue::new(3, 1));
+        assert_ne!(value_differs, same_order);
+    }
+
+    #[test]
+    fn test_valord_debug_skips_removed() {
+        let mut valord = ValordMap::new();
+        valord.insert(1, "a");
+        valord.insert(2, "b");
+        valord.insert(3, "c");
+        valord.remove(&2);
+
+        assert_eq!(format!("{valord:?}"), r#"{1: "a", 3: "c"}"#);
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Score(u32);
+
+    impl OrdBy for Score {
+        type Target = Reverse<u32>;
+
+        fn ord_by(&self) -> Self::Target {
+            Reverse(self.0)
+        }
+    }
+
+    #[test]
+    fn test_valord_reverse_target_first() {
+        let mut valord = ValordMap::new();
+        valord.insert("qians", Score(10));
+        valord.insert("tedious", Score(30));
+        valord.insert("xuandu", Score(20));
+
+        assert_eq!(valord.first(), vec![(&"tedious", &Score(30))]);
+        assert_eq!(valord.last(), vec![(&"qians", &Score(10))]);
+        assert_eq!(
+            valord.iter().map(|(_, v)| v.0).collect::<Vec<_>>(),
+            vec![30, 20, 10]
+        );
+    }
+
+    #[test]
+    fn test_valord_reverse_target_range() {
+        let mut valord = ValordMap::new();
+        (1..=5).for_each(|i| valord.insert(i, Score(i * 10)));
+
+        let scores =
+            |range: Vec<(&u32, &Score)>| range.iter().map(|(_, v)| v.0).collect::<Vec<_>>();
+
+        assert_eq!(
+            scores(valord.range(Reverse(30)..).collect()),
+            vec![30, 20, 10]
+        );
+        assert_eq!(scores(valord.range(..Reverse(30)).collect()), vec![50, 40]);
+        assert_eq!(
+            scores(valord.range(Reverse(40)..=Reverse(20)).collect()),
+            vec![40, 30, 20]
+        );
+        assert!(valord.range(Reverse(60)..Reverse(50)).next().is_none());
+    }
+
+    #[test]
+    fn test_valord_keys_values_follow_resort() {
+        let mut valord = ValordMap::new();
+        valord.insert("qians", OrdByValue::new(1, 1));
+        valord.insert("tedious", OrdByValue::new(2, 2));
+        valord.insert("xuandu", OrdByValue::new(3, 3));
+
+        valord
+            .values_mut()
+            .filter(|v| v.sth != 2)
+            .for_each(|mut v| v.order_by *= 10);
+
+        assert_eq!(
+            valord.keys().collect::<Vec<_>>(),
+            vec![&"tedious", &"qians", &"xuandu"]
+        );
+        assert_eq!(
+            valord.rev_values().map(|v| v.order_by).collect::<Vec<_>>(),
+            vec![30, 10, 2]
+        );
+
+        valord
+            .rev_values_mut()
+            .take(1)
+            .for_each(|mut v| v.order_by = 0);
+
+        assert_eq!(
+            valord
+                .iter()
+                .map(|(k, v)| (*k, v.order_by))
+                .collect::<Vec<_>>(),
+            vec![("xuandu", 0), ("tedious", 2), ("qians", 10)]
+        );
+        assert_eq!(
+            valord.rev_keys().collect::<Vec<_>>(),
+            vec![&"qians", &"tedious", &"xuandu"]
+        );
+    }
+
+    #[test]
+    fn test_valord_insert_many_replace() {
+        let mut valord = ValordMap::new();
+        valord.insert("qians", 1);
+        valord.insert("tedious", 2);
+        valord.insert("xuandu", 3);
+        valord.remove(&"tedious");
+
+        let replaced =
+            valord.insert_many_replace([("xuandu", 0), ("tedious", 5), ("sheng", 4), ("qians", 6)]);
+
+        assert_eq!(
+            replaced,
+            vec![
+                ("xuandu", Some(3)),
+                ("tedious", None),
+                ("sheng", None),
+                ("qians", Some(1))
+            ]
+        );
+        assert_eq!(valord.len(), 4);
+        assert_eq!(
+            valord.iter().collect::<Vec<_>>(),
+            vec![
+                (&"xuandu", &0),
+                (&"sheng", &4),
+                (&"tedious", &5),
+                (&"qians", &6)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_valord_reinsert_after_remove() {
+        let mut valord = ValordMap::new();
+        valord.insert("qians", 1);
+        valord.insert("tedious", 2);
+        valord.remove(&"qians");
+
+        valord.insert("xuandu", 3);
+        valord.insert("qians", 4);
+
+        assert_eq!(valord.get(&"qians"), Some(&4));
+        assert_eq!(valord.get(&"xuandu"), Some(&3));
+        assert_eq!(valord.get(&"tedious"), Some(&2));
+        assert_eq!(valord.len(), 3);
+
+        valord.insert("xuandu", 0);
+        assert_eq!(valord.first(), vec![(&"xuandu", &0)]);
+        assert_eq!(valord.len(), 3);
+    }
+
+    #[test]
+    fn test_valord_new_keys_reuse_freed_slots() {
+        let mut valord = ValordMap::new();
+        (0..8).for_each(|i| valord.insert(i, i));
+
+        for i in 8..1000 {
+            valord.remove(&(i - 8));
+            valord.insert(i, i);
+        }
+
+        assert_eq!(valord.len(), 8);
+        assert_eq!(valord.map.len(), 8);
+        assert!(valord.free_indexs.is_empty());
+        assert_eq!(
+            valord.keys().copied().collect::<Vec<_>>(),
+            (992..1000).collect::<Vec<_>>()
+        );
+        assert!((992..1000).all(|i| valord.get(&i) == Some(&i)));
+        assert!((0..992).all(|i| valord.get(&i).is_none()));
+
+        // A removed key still gets its own slot back while it is free.
+        let index = valord.map.get_index_of(&995);
+        valord.remove(&995);
+        valord.insert(995, 0);
+        assert_eq!(valord.map.get_index_of(&995), index);
+        assert_eq!(valord.first(), vec![(&995, &0)]);
+    }
+
+    #[test]
+    fn test_valord_get_disjoint_mut_swap() {
+        let mut valord = ValordMap::new();
+        valord.insert("qians", OrdByValue::new(1, 10));
+        valord.insert("tedious", OrdByValue::new(2, 20));
+        valord.insert("xuandu", OrdByValue::new(3, 30));
+
+        let [mut a, mut b] = valord.get_disjoint_mut([&"qians", &"xuandu"]).unwrap();
+        std::mem::swap(&mut a.order_by, &mut b.order_by);
+        drop([a, b]);
+
+        assert_eq!(
+            valord
+                .iter()
+                .map(|(k, v)| (*k, v.order_by))
+                .collect::<Vec<_>>(),
+            vec![("xuandu", 10), ("tedious", 20), ("qians", 30)]
+        );
+        assert_eq!(valord.range(30..).count(), 1);
+
+        let [c] = valord.get_disjoint_mut([&"tedious"]).unwrap();
+        assert_eq!(c.sth, 2);
+        drop(c);
+
+        assert!(valord.get_disjoint_mut([&"tedious", &"tedious"]).is_none());
+        valord.remove(&"tedious");
+        assert!(valord.get_disjoint_mut([&"qians", &"tedious"]).is_none());
+        assert_eq!(valord.len(), 2);
+    }
+
+    #[test]
+    fn test_valord_get_disjoint_mut_drop_orders() {
+        let drop_orders = [
+            [0, 1, 2],
+            [0, 2, 1],
+            [1, 0, 2],
+            [1, 2, 0],
+            [2, 0, 1],
+            [2, 1, 0],
+        ];
+        for order in drop_orders {
+            let mut valord = ValordMap::new();
+            valord.insert("qians", 1);
+            valord.insert("tedious", 2);
+            valord.insert("xuandu", 3);
+            valord.insert("sheng", 4);
+
+            let entries = valord
+                .get_disjoint_mut([&"qians", &"tedious", &"xuandu"])
+                .unwrap();
+            let mut entries = entries.map(Some);
+            for (i, entry) in entries.iter_mut().enumerate() {
+                **entry.as_mut().unwrap() += 10 * (3 - i as i32);
+            }
+
+            // Keep the last-dropped entry's value borrowed while the others drop.
+            let last = order[2];
+            let mut kept = entries[last].take().unwrap();
+            let kept_value = &mut *kept;
+            for i in &order[..2] {
+                drop(entries[*i].take());
+            }
+            *kept_value += 100;
+            drop(kept);
+            drop(entries);
+
+            let mut expected = vec![(&"qians", 31), (&"tedious", 22), (&"xuandu", 13)];
+            expected[last].1 += 100;
+            expected.push((&"sheng", 4));
+            expected.sort_by_key(|(_, v)| *v);
+            assert_eq!(
+                valord.iter().map(|(k, v)| (k, *v)).collect::<Vec<_>>(),
+                expected,
+                "drop order {order:?}"
+            );
+            assert_eq!(valord.len(), 4);
+            assert_eq!(
+                valord
+                    .sorted_indexs
+                    .values()
+                    .map(HashSet::len)
+                    .sum::<usize>(),
+                4
+            );
+        }
+    }
+
+    #[test]
+    fn test_valord_ordering_fingerprint_insertion_order_independent() {
+        let pairs = [
+            ("qians", 3),
+            ("tedious", 1),
+            ("xuandu", 3),
+            ("sheng", 2),
+            ("x", 1),
+        ];
+
+        let mut forward = ValordMap::new();
+        pairs.iter().for_each(|(k, v)| forward.insert(*k, *v));
+
+        let mut backward = ValordMap::new();
+        pairs
+            .iter()
+            .rev()
+            .for_each(|(k, v)| backward.insert(*k, *v));
+
+        let mut with_removed = ValordMap::new();
+        with_removed.insert("removed", 0);
+        pairs.iter().for_each(|(k, v)| with_removed.insert(*k, *v));
+        with_removed.remove(&"removed");
+
+        assert_eq!(
+            forward.ordering_fingerprint(),
+            backward.ordering_fingerprint()
+        );
+        assert_eq!(
+            forward.ordering_fingerprint(),
+            with_removed.ordering_fingerprint()
+        );
+
+        backward.insert("sheng", 1);
+        assert_ne!(
+            forward.ordering_fingerprint(),
+            backward.ordering_fingerprint()
+        );
+
+        assert_eq!(
+            ValordMap::<usize, usize, usize>::new().ordering_fingerprint(),
+            ValordMap::<usize, usize, usize>::new().ordering_fingerprint()
+        );
+    }
+
+    #[test]
+    fn test_valord_shrink_to_fit() {
+        let mut valord = ValordMap::new();
+        (0..20).for_each(|i| valord.insert(i, OrdByValue::new(i, (i * 7) % 20)));
+        (0..20).filter(|i| i % 2 == 0).for_each(|i| {
+            valord.remove(&i);
+        });
+
+        let before: Vec<_> = valord.iter().map(|(k, v)| (*k, v.order_by)).collect();
+        assert_eq!(valord.map.len(), 20);
+
+        valord.shrink_to_fit();
+
+        assert_eq!(valord.map.len(), valord.len());
+        assert!(valord.map.values().all(Option::is_some));
+        assert!(valord.free_indexs.is_empty());
+        assert_eq!(
+            valord
+                .iter()
+                .map(|(k, v)| (*k, v.order_by))
+                .collect::<Vec<_>>(),
+            before
+        );
+
+        valord.insert(0, OrdByValue::new(0, 0));
+        assert_eq!(valord.first(), vec![(&0, &OrdByValue::new(0, 0))]);
+        assert_eq!(valord.get(&1), Some(&OrdByValue::new(1, 7)));
+        assert_eq!(valord.len(), 11);
+    }
+
+    #[test]
+    fn test_valord_into_split_at_rank() {
+        let mut valord = ValordMap::new();
+        (0..12).for_each(|i| valord.insert(i, 100 - i * 10));
+        valord.remove(&10);
+        valord.remove(&11);
+
+        let (smallest, rest) = valord.into_split_at_rank(4);
+
+        assert_eq!(smallest.len(), 4);
+        assert_eq!(rest.len(), 6);
+        assert_eq!(
+            smallest.iter().collect::<Vec<_>>(),
+            vec![(&9, &10), (&8, &20), (&7, &30), (&6, &40)]
+        );
+        assert_eq!(
+            rest.values().collect::<Vec<_>>(),
+            vec![&50, &60, &70, &80, &90, &100]
+        );
+        assert_eq!(smallest.map.len(), 4);
+        assert_eq!(rest.map.len(), 6);
+
+        let (all, none) = smallest.into_split_at_rank(10);
+        assert_eq!(all.len(), 4);
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn test_valord_dropped_vacant_entry_leaves_map_untouched() {
+        let mut valord = ValordMap::new();
+        valord.insert("qians", 1);
+        valord.insert("tedious", 2);
+        valord.remove(&"tedious");
+
+        drop(valord.entry("xuandu"));
+        drop(valord.entry("tedious"));
+
+        assert!(!valord.contains_key(&"xuandu"));
+        assert!(!valord.contains_key(&"tedious"));
+        assert!(!valord.map.contains_key(&"xuandu"));
+        assert_eq!(valord.map.len(), 2);
+        assert_eq!(valord.free_indexs.len(), 1);
+        assert_eq!(valord.len(), 1);
+
+        valord.insert("sheng", 3);
+        valord.entry("xuandu").or_insert(4);
+        valord.entry("tedious").or_insert(5);
+
+        assert_eq!(valord.get(&"qians"), Some(&1));
+        assert_eq!(valord.get(&"sheng"), Some(&3));
+        assert_eq!(valord.get(&"xuandu"), Some(&4));
+        assert_eq!(valord.get(&"tedious"), Some(&5));
+        assert!(valord.free_indexs.is_empty());
+        assert_eq!(valord.len(), 4);
+        assert_eq!(
+            valord.iter().collect::<Vec<_>>(),
+            vec![
+                (&"qians", &1),
+                (&"sheng", &3),
+                (&"xuandu", &4),
+                (&"tedious", &5)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_valord_vacant_entry_resorts_on_drop() {
+        let mut valord = ValordMap::new();
+        valord.insert("qians", 5);
+
+        let mut entry = valord.entry("tedious");
+        assert!(matches!(&entry, Entry::Vacant(vacant) if vacant.key() == &"tedious"));
+        *entry.or_insert(1) += 9;
+        assert_eq!(*entry.or_insert(0), 10);
+        drop(entry);
+
+        assert_eq!(valord.len(), 2);
+        assert_eq!(
+            valord.iter().collect::<Vec<_>>(),
+            vec![(&"qians", &5), (&"tedious", &10)]
+        );
+    }
+
+    #[test]
+    fn test_valord_get_mut_pair_transfer() {
+        let mut valord = ValordMap::new();
+        valord.insert("qians", OrdByValue::new(1, 30));
+        valord.insert("tedious", OrdByValue::new(2, 20));
+        valord.insert("xuandu", OrdByValue::new(3, 10));
+
+        let (mut from, mut to) = valord.get_mut_pair(&"qians", &"xuandu").unwrap();
+        from.order_by -= 25;
+        to.order_by += 25;
+        drop(from);
+        drop(to);
+
+        assert_eq!(
+            valord
+                .iter()
+                .map(|(k, v)| (*k, v.order_by))
+                .collect::<Vec<_>>(),
+            vec![("qians", 5), ("tedious", 20), ("xuandu", 35)]
+        );
+        assert!(valord.get_mut_pair(&"qians", &"sheng").is_none());
+        assert!(valord.get_mut_pair(&"tedious", &"tedious").is_none());
+    }
+
+    #[test]
+    fn test_valord_from_iter_and_extend() {
+        let pairs = [
+            ("qians", OrdByValue::new(1, 2)),
+            ("tedious", OrdByValue::new(2, 1)),
+            ("qians", OrdByValue::new(3, 3)),
+            ("xuandu", OrdByValue::new(4, 1)),
+        ];
+
+        let mut collected: ValordMap<_, _, _> = pairs.into_iter().collect();
+
+        let mut inserted = ValordMap::new();
+        [
+            ("qians", OrdByValue::new(1, 2)),
+            ("tedious", OrdByValue::new(2, 1)),
+            ("qians", OrdByValue::new(3, 3)),
+            ("xuandu", OrdByValue::new(4, 1)),
+        ]
+        .into_iter()
+        .for_each(|(k, v)| inserted.insert(k, v));
+
+        assert_eq!(collected, inserted);
+        assert_eq!(collected.len(), 3);
+        assert_eq!(collected.first().len(), 2);
+        assert_eq!(collected.last(), vec![(&"qians", &OrdByValue::new(3, 3))]);
+
+        collected.extend([
+            ("tedious", OrdByValue::new(5, 4)),
+            ("sheng", OrdByValue::new(6, 3)),
+        ]);
+
+        assert_eq!(collected.len(), 4);
+        assert_eq!(
+            collected.values().map(|v| v.order_by).collect::<Vec<_>>(),
+            vec![1, 3, 3, 4]
+        );
+        assert_eq!(collected.last(), vec![(&"tedious", &OrdByValue::new(5, 4))]);
+        assert_eq!(collected.first(), vec![(&"xuandu", &OrdByValue::new(4, 1))]);
+    }
+
+    #[test]
+    fn test_valord_iter_ranked() {
+        let mut valord = ValordMap::new();
+        valord.insert("a", 1);
+        valord.insert("b", 1);
+        valord.insert("c", 1);
+        valord.insert("d", 2);
+        valord.insert("e", 3);
+        valord.insert("f", 3);
+        valord.remove(&"b");
+
+        let ranks: Vec<_> = valord
+            .iter_ranked()
+            .map(|(rank, _, v)| (rank, *v))
+            .collect();
+        assert_eq!(ranks, vec![(0, 1), (0, 1), (2, 2), (3, 3), (3, 3)]);
+        assert_eq!(
+            valord
+                .iter_ranked()
+                .map(|(_, k, v)| (k, v))
+                .collect::<Vec<_>>(),
+            valord.iter().collect::<Vec<_>>()
+        );
+        assert!(ValordMap::<usize, usize, usize>::new()
+            .iter_ranked()
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn test_valord_count_and_entries_at() {
+        let mut valord = ValordMap::new();
+        valord.insert("qians", OrdByValue::new(1, 5));
+        valord.insert("tedious", OrdByValue::new(2, 5));
+        valord.insert("xuandu", OrdByValue::new(3, 5));
+        valord.insert("sheng", OrdByValue::new(4, 7));
+        valord.remove(&"tedious");
+
+        assert_eq!(valord.count_at(&5), 2);
+        assert_eq!(valord.count_at(&7), 1);
+        assert_eq!(valord.count_at(&6), 0);
+
+        let mut ties: Vec<_> = valord.entries_at(&5).map(|(_, v)| v.sth).collect();
+        ties.sort();
+        assert_eq!(ties, vec![1, 3]);
+        assert_eq!(valord.entries_at(&6).count(), 0);
+
+        valord.remove(&"sheng");
+        assert_eq!(valord.count_at(&7), 0);
+        assert_eq!(valord.entries_at(&7).count(), 0);
+    }
+
+    #[test]
+    fn test_valord_new_by_borrowing_closure() {
+        // Neither `Send`, `Sync` nor `'static`.
+        let calls = Cell::new(0);
+        let mut valord = ValordMap::new_by(|pair: &(u32, u32)| {
+            calls.set(calls.get() + 1);
+            &pair.1
+        });
+        valord.insert("qians", (1, 30));
+        valord.insert("tedious", (2, 10));
+        valord.insert("xuandu", (3, 20));
+
+        assert_eq!(
+            valord.keys().collect::<Vec<_>>(),
+            vec![&"tedious", &"xuandu", &"qians"]
+        );
+        assert_eq!(calls.get(), 3);
+        assert_eq!(valord.clone(), valord);
+    }
+
+    #[test]
+    fn test_valord_new_by_closure() {
+        use std::ops::Range;
+
+        let mut valord = ValordMap::new_by(|range: &Range<u32>| &range.start);
+        valord.insert("qians", 30..40);
+        valord.insert("tedious", 10..90);
+        valord.insert("xuandu", 20..21);
+
+        assert_eq!(
+            valord.keys().collect::<Vec<_>>(),
+            vec![&"tedious", &"xuandu", &"qians"]
+        );
+        assert_eq!(valord.first(), vec![(&"tedious", &(10..90))]);
+        assert_eq!(
+            valord.range(15..=30).map(|(k, _)| *k).collect::<Vec<_>>(),
+            vec!["xuandu", "qians"]
+        );
+
+        valord.get_mut(&"tedious").unwrap().start = 50;
+        assert!(valord.modify(&"xuandu", |range| *range = 60..70));
+        valord.entry("sheng").or_insert(0..1);
+        valord.remove(&"qians");
+
+        assert_eq!(
+            valord
+                .iter()
+                .map(|(k, v)| (*k, v.start))
+                .collect::<Vec<_>>(),
+            vec![("sheng", 0), ("tedious", 50), ("xuandu", 60)]
+        );
+
+        let (smallest, rest) = valord.into_split_at_rank(1);
+        assert_eq!(smallest.keys().collect::<Vec<_>>(), vec![&"sheng"]);
+        assert_eq!(rest.last(), vec![(&"xuandu", &(60..70))]);
+    }
+
+    #[test]
+    fn test_valord_append_overlapping() {
+        let mut valord = ValordMap::new();
+        valord.insert("qians", OrdByValue::new(1, 2));
+        valord.insert("tedious", OrdByValue::new(2, 5));
+        valord.insert("xuandu", OrdByValue::new(3, 5));
+        valord.remove(&"xuandu");
+
+        let mut other = ValordMap::new();
+        other.insert("sheng", OrdByValue::new(4, 2));
+        other.insert("tedious", OrdByValue::new(5, 1));
+        other.insert("xuandu", OrdByValue::new(6, 5));
+        other.insert("yu", OrdByValue::new(7, 9));
+        other.remove(&"yu");
+
+        valord.append(&mut other);
+
+        assert!(other.is_empty());
+        assert!(other.sorted_indexs.is_empty());
+        assert!(other.free_indexs.is_empty());
+        assert_eq!(valord.len(), 4);
+        assert_eq!(
+            valord.values().map(|v| v.order_by).collect::<Vec<_>>(),
+            vec![1, 2, 2, 5]
+        );
+        assert_eq!(valord.first(), vec![(&"tedious", &OrdByValue::new(5, 1))]);
+        assert_eq!(valord.last(), vec![(&"xuandu", &OrdByValue::new(6, 5))]);
+        assert_eq!(valord.count_at(&2), 2);
+
+        other.insert("qians", OrdByValue::new(8, 0));
+        assert_eq!(other.len(), 1);
+        valord.append(&mut other);
+        assert_eq!(valord.len(), 4);
+        assert_eq!(valord.first(), vec![(&"qians", &OrdByValue::new(8, 0))]);
+    }
+
+    #[test]
+    fn test_valord_insert_keep_best_higher_wins() {
+        let higher = |new: &OrdByValue, old: &OrdByValue| new.order_by > old.order_by;
+
+        let mut valord = ValordMap::new();
+        assert!(valord.insert_keep_best("qians", OrdByValue::new(1, 10), higher));
+        assert!(valord.insert_keep_best("tedious", OrdByValue::new(2, 20), higher));
+        assert!(!valord.insert_keep_best("qians", OrdByValue::new(3, 5), higher));
+        assert!(!valord.insert_keep_best("qians", OrdByValue::new(4, 10), higher));
+        assert!(valord.insert_keep_best("qians", OrdByValue::new(5, 30), higher));
+        assert!(!valord.insert_keep_best("tedious", OrdByValue::new(6, 15), higher));
+
+        assert_eq!(valord.len(), 2);
+        assert_eq!(
+            valord.iter().collect::<Vec<_>>(),
+            vec![
+                (&"tedious", &OrdByValue::new(2, 20)),
+                (&"qians", &OrdByValue::new(5, 30))
+            ]
+        );
+        assert_eq!(valord.count_at(&10), 0);
+
+        valord.remove(&"qians");
+        assert!(valord.insert_keep_best("qians", OrdByValue::new(7, 1), higher));
+        assert_eq!(valord.first(), vec![(&"qians", &OrdByValue::new(7, 1))]);
+    }
+
+    #[test]
+    fn test_valord_range_count_bounds() {
+        use std::ops::Bound::{Excluded, Included, Unbounded};
+
+        let mut valord = ValordMap::new();
+        valord.insert("a", 1);
+        valord.insert("b", 2);
+        valord.insert("c", 2);
+        valord.insert("d", 3);
+        valord.insert("e", 3);
+        valord.insert("f", 3);
+        valord.insert("g", 5);
+        valord.remove(&"f");
+
+        assert_eq!(valord.range_count(..), 6);
+        assert_eq!(valord.range_count(2..3), 2);
+        assert_eq!(valord.range_count(2..=3), 4);
+        assert_eq!(valord.range_count(3..), 3);
+        assert_eq!(valord.range_count(..3), 3);
+        assert_eq!(valord.range_count(..=3), 5);
+        assert_eq!(valord.range_count((Excluded(2), Included(5))), 3);
+        assert_eq!(valord.range_count((Excluded(1), Excluded(3))), 2);
+        assert_eq!(valord.range_count((Excluded(3), Unbounded)), 1);
+        assert_eq!(valord.range_count(4..5), 0);
+        assert_eq!(valord.range_count(6..), 0);
+        assert_eq!(valord.range_count(3..3), 0);
+
+        for (lo, hi) in [(0, 6), (1, 3), (2, 5), (3, 4)] {
+            assert_eq!(valord.range_count(lo..hi), valord.range(lo..hi).count());
+        }
+    }
+
+    #[test]
+    fn test_valord_into_iter_matches_iter() {
+        let mut valord = ValordMap::new();
+        valord.insert("qians", OrdByValue::new(1, 3));
+        valord.insert("tedious", OrdByValue::new(2, 1));
+        valord.insert("xuandu", OrdByValue::new(3, 2));
+        valord.insert("sheng", OrdByValue::new(4, 4));
+        valord.remove(&"xuandu");
+        valord.insert("xuandu", OrdByValue::new(5, 0));
+
+        let expected: Vec<_> = valord.iter().map(|(k, v)| (*k, v.sth)).collect();
+        let mut into_iter = valord.into_iter();
+        assert_eq!(into_iter.len(), 4);
+        assert_eq!(
+            into_iter.next_back(),
+            Some(("sheng", OrdByValue::new(4, 4)))
+        );
+        assert_eq!(into_iter.len(), 3);
+        assert_eq!(
+            into_iter.map(|(k, v)| (k, v.sth)).collect::<Vec<_>>(),
+            expected[..3]
+        );
+    }
+
+    #[test]
+    fn test_valord_new_desc_order() {
+        let mut valord = ValordMap::new_desc();
+        valord.insert("qians", 1);
+        valord.insert("tedious", 2);
+        valord.insert("sheng", 3);
+        valord.insert("xuandu", 4);
+        valord.insert("xuandu2", 5);
+        valord.insert("xuandu3", 6);
+
+        assert_eq!(valord.first(), vec![(&"xuandu3", &6)]);
+        assert_eq!(valord.last(), vec![(&"qians", &1)]);
+        assert_eq!(
+            valord.values().collect::<Vec<_>>(),
+            vec![&6, &5, &4, &3, &2, &1]
+        );
+        assert_eq!(
+            valord.rev_keys().collect::<Vec<_>>(),
+            vec![&"qians", &"tedious", &"sheng", &"xuandu", &"xuandu2", &"xuandu3"]
+        );
+        assert_eq!(
+            valord.range(Reverse(5)..Reverse(2)).collect::<Vec<_>>(),
+            vec![(&"xuandu2", &5), (&"xuandu", &4), (&"sheng", &3)]
+        );
+        assert_eq!(valord.range(..=Reverse(5)).count(), 2);
+        assert_eq!(valord.range_count(Reverse(3)..), 3);
+
+        let mut range_iter = valord.range_mut(Reverse(4)..);
+        let mut item = range_iter.next().unwrap();
+        let (k, v) = item.get_mut_with_key();
+        assert_eq!(k, &"xuandu");
+        *v += 4;
+        drop(item);
+        drop(range_iter);
+        assert_eq!(valord.first(), vec![(&"xuandu", &8)]);
+
+        assert!(valord.modify(&"qians", |v| *v = 7));
+        assert_eq!(
+            valord.keys().take(3).collect::<Vec<_>>(),
+            vec![&"xuandu", &"qians", &"xuandu3"]
+        );
+        assert_eq!(valord.last(), vec![(&"tedious", &2)]);
+
+        let (top, rest) = valord.into_split_at_rank(2);
+        assert_eq!(top.values().collect::<Vec<_>>(), vec![&8, &7]);
+        assert_eq!(rest.first(), vec![(&"xuandu3", &6)]);
+    }
+
+    #[test]
+    fn test_valord_compact_ordered_indexes_follow_order() {
+        let mut valord = ValordMap::new();
+        (0..10).for_each(|i| valord.insert(i, OrdByValue::new(i, (i * 7) % 10)));
+        valord.remove(&3);
+        valord.remove(&8);
+        assert!(valord.modify(&0, |v| v.order_by = 11));
+
+        let expected: Vec<_> = valord.keys().copied().collect();
+        let compact = valord.compact_ordered();
+
+        assert_eq!(compact.len(), 8);
+        assert_eq!(compact.map.len(), 8);
+        assert!(compact.free_indexs.is_empty());
+        assert_eq!(compact.keys().copied().collect::<Vec<_>>(), expected);
+        let indexes: Vec<_> = expected
+            .iter()
+            .map(|k| compact.map.get_index_of(k).unwrap())
+            .collect();
+        assert_eq!(indexes, (0..8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_valord_case_insensitive_names() {
+        #[derive(Debug, PartialEq)]
+        struct Player {
+            name: String,
+        }
+
+        impl OrdBy for Player {
+            type Target = CaseInsensitive<String>;
+
+            fn ord_by(&self) -> Self::Target {
+                CaseInsensitive(self.name.clone())
+            }
+        }
+
+        let mut valord = ValordMap::new();
+        for (id, name) in [(1, "bob"), (2, "Alice"), (3, "CHARLIE"), (4, "alice")] {
+            valord.insert(
+                id,
+                Player {
+                    name: name.to_string(),
+                },
+            );
+        }
+
+        assert_eq!(
+            valord
+                .values()
+                .map(|p| p.name.to_lowercase())
+                .collect::<Vec<_>>(),
+            vec!["alice", "alice", "bob", "charlie"]
+        );
+        assert_eq!(valord.count_at(&CaseInsensitive("ALICE".to_string())), 2);
+        assert_eq!(valord.last()[0].1.name, "CHARLIE");
+        assert_eq!(
+            valord
+                .range(CaseInsensitive("B".to_string())..)
+                .map(|(k, _)| *k)
+                .collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        assert!(CaseInsensitive("Straße") > CaseInsensitive("STRASSE"));
+        assert!(CaseInsensitive("ab") < CaseInsensitive("AbC"));
+    }
+
+    #[test]
+    fn test_valord_update_value_skips_equal_ord_by() {
+        let mut valord = ValordMap::new();
+        valord.insert("qians", OrdByValue::new(1, 5));
+        valord.insert("tedious", OrdByValue::new(2, 7));
+        valord.sorted_indexs.get_mut(&5).unwrap().reserve(64);
+        let capacity = valord.sorted_indexs[&5].capacity();
+
+        assert_eq!(
+            valord.update_value(&"qians", OrdByValue::new(3, 5)),
+            Some(OrdByValue::new(1, 5))
+        );
+        // Same ordering value: the bucket was left in place, not rebuilt.
+        assert_eq!(valord.sorted_indexs[&5].capacity(), capacity);
+        assert_eq!(valord.first(), vec![(&"qians", &OrdByValue::new(3, 5))]);
+
+        assert_eq!(
+            valord.update_value(&"qians", OrdByValue::new(4, 9)),
+            Some(OrdByValue::new(3, 5))
+        );
+        assert!(!valord.sorted_indexs.contains_key(&5));
+        assert_eq!(valord.count_at(&9), 1);
+        assert_eq!(valord.last(), vec![(&"qians", &OrdByValue::new(4, 9))]);
+        assert_eq!(
+            valord.keys().collect::<Vec<_>>(),
+            vec![&"tedious", &"qians"]
+        );
+
+        valord.remove(&"tedious");
+        assert_eq!(valord.update_value(&"tedious", OrdByValue::new(5, 1)), None);
+        assert_eq!(valord.len(), 1);
+    }
+
+    #[test]
+    fn test_valord_into_grouped_matches_contents() {
+        let mut valord = ValordMap::new();
+        (0..9).for_each(|i| valord.insert(i, OrdByValue::new(i, i % 3)));
+        valord.remove(&4);
+        valord.remove(&7);
+        valord.remove(&1);
+        valord.insert(1, OrdByValue::new(10, 5));
+
+        let tiers: Vec<_> = valord
+            .to_bucket_deque()
+            .into_iter()
+            .map(|(t, _)| t)
+            .collect();
+        let mut expected: Vec<_> = valord.iter().map(|(k, v)| (v.order_by, *k)).collect();
+        expected.sort();
+
+        let grouped = valord.into_grouped();
+        assert_eq!(grouped.iter().map(|(t, _)| *t).collect::<Vec<_>>(), tiers);
+        assert_eq!(tiers, vec![0, 2, 5]);
+
+        let mut flattened: Vec<_> = grouped
+            .into_iter()
+            .flat_map(|(target, entries)| {
+                entries.into_iter().map(move |(k, v)| {
+                    assert_eq!(v.order_by, target);
+                    (target, k)
+                })
+            })
+            .collect();
+        flattened.sort();
+        assert_eq!(flattened, expected);
+        assert_eq!(flattened.len(), 7);
+    }
+
+    #[test]
+    fn test_valord_aggregate_ord_by_recomputed() {
+        #[derive(Debug, PartialEq)]
+        struct Basket(Vec<u32>);
+
+        impl OrdBy for Basket {
+            type Target = u32;
+
+            fn ord_by(&self) -> u32 {
+                self.0.iter().sum()
+            }
+        }
+
+        let mut valord = ValordMap::new();
+        valord.insert("qians", Basket(vec![3, 4]));
+        valord.insert("tedious", Basket(vec![1, 1, 1]));
+        valord.insert("xuandu", Basket(vec![10]));
+        assert_eq!(valord.range_count(..8), 2);
+
+        valord.get_mut(&"tedious").unwrap().0.push(20);
+        assert!(valord.modify(&"xuandu", |b| b.0.clear()));
+        valord
+            .iter_mut()
+            .filter(|entry| entry.0.len() == 2)
+            .for_each(|mut entry| entry.0[1] = 11);
+
+        assert_eq!(
+            valord
+                .iter()
+                .map(|(k, b)| (*k, b.ord_by()))
+                .collect::<Vec<_>>(),
+            vec![("xuandu", 0), ("qians", 14), ("tedious", 23)]
+        );
+        assert_eq!(valord.count_at(&14), 1);
+        assert_eq!(valord.count_at(&7), 0);
+        assert_eq!(valord.count_at(&3), 0);
+    }
+
+    #[test]
+    fn test_valord_adjust_first_and_last_buckets() {
+        let mut valord = ValordMap::new();
+        (0..6).for_each(|i| valord.insert(i, OrdByValue::new(i, 1)));
+        valord.insert(6, OrdByValue::new(6, 4));
+        valord.insert(7, OrdByValue::new(7, 9));
+        valord.insert(8, OrdByValue::new(8, 9));
+        valord.remove(&5);
+
+        let mut visited = Vec::new();
+        valord.adjust_first(|k, v| {
+            visited.push(*k);
+            if k % 2 == 0 {
+                v.order_by = 4;
+            }
+        });
+        visited.sort();
+        assert_eq!(visited, vec![0, 1, 2, 3, 4]);
+
+        let mut first: Vec<_> = valord.first().into_iter().map(|(k, _)| *k).collect();
+        first.sort();
+        assert_eq!(first, vec![1, 3]);
+        assert_eq!(valord.count_at(&4), 4);
+        assert_eq!(valord.len(), 8);
+
+        valord.adjust_last(|_, v| v.order_by = 0);
+        assert_eq!(valord.first().len(), 2);
+        assert_eq!(valord.last().len(), 4);
+        assert_eq!(
+            valord.values().map(|v| v.order_by).collect::<Vec<_>>(),
+            vec![0, 0, 1, 1, 4, 4, 4, 4]
+        );
+
+        valord.adjust_last(|_, _| {});
+        assert_eq!(valord.count_at(&4), 4);
+    }
+
+    #[test]
+    fn test_valord_retain_in_set_subset() {
+        let mut valord = ValordMap::new();
+        (0..10).for_each(|i| valord.insert(i, OrdByValue::new(i, 10 - i / 2)));
+        valord.remove(&4);
+        let indexs: Vec<_> = (0..10).map(|i| valord.map.get_index_of(&i)).collect();
+
+        valord.retain_in_set(&HashSet::from([0, 2, 3, 4, 7, 8, 42]));
+
+        assert_eq!(valord.len(), 5);
+        assert_eq!(
+            valord.values().map(|v| v.order_by).collect::<Vec<_>>(),
+            vec![6, 7, 9, 9, 10]
+        );
+        assert_eq!(valord.last(), vec![(&0, &OrdByValue::new(0, 10))]);
+        assert_eq!(valord.count_at(&8), 0);
+        assert!(!valord.contains_key(&1));
+        assert!(!valord.contains_key(&4));
+
+        valord.insert(1, OrdByValue::new(1, 0));
+        assert_eq!(valord.first(), vec![(&1, &OrdByValue::new(1, 0))]);
+
+        assert!([0, 2, 3, 7, 8]
+            .iter()
+            .all(|&i| valord.map.get_index_of(&i) == indexs[i]));
+        assert_eq!(valord.map.len(), 10);
+
+        valord.retain_in_set(&(0..100).filter(|i| i % 4 != 3).collect());
+        assert_eq!(valord.keys().copied().collect::<Vec<_>>(), vec![1, 8, 2, 0]);
+
+        valord.retain_in_set(&HashSet::from([8, 9]));
+        assert_eq!(valord.keys().collect::<Vec<_>>(), vec![&8]);
+        assert_eq!(valord.map.get_index_of(&8), indexs[8]);
+        assert_eq!(valord.map.len(), 10);
+
+        valord.retain_in_set(&HashSet::new());
+        assert!(valord.is_empty());
+        assert!(valord.sorted_indexs.is_empty());
+    }
+
+    #[test]
+    fn test_valord_prefix_range_bytes() {
+        let mut valord = ValordMap::new();
+        valord.insert("a", vec![0x10]);
+        valord.insert("b", vec![0x10, 0x20]);
+        valord.insert("c", vec![0x10, 0x20, 0x00]);
+        valord.insert("d", vec![0x10, 0x20, 0xff]);
+        valord.insert("e", vec![0x10, 0x21]);
+        valord.insert("f", vec![0x0f, 0xff]);
+        valord.insert("g", vec![0x10, 0x20, 0x00]);
+        valord.remove(&"d");
+
+        let mut scanned: Vec<_> = valord.prefix_range(&[0x10, 0x20][..]).collect();
+        assert_eq!(scanned.len(), 3);
+        assert_eq!(scanned.remove(0), (&"b", &vec![0x10, 0x20]));
+        assert!(scanned.iter().all(|(_, v)| **v == [0x10, 0x20, 0x00]));
+
+        assert_eq!(valord.prefix_range(&[0x10][..]).count(), 5);
+        assert_eq!(valord.prefix_range(&[][..]).count(), 6);
+        assert_eq!(valord.prefix_range(&[0x0f, 0xff, 0x00][..]).count(), 0);
+        assert_eq!(valord.prefix_range(&[0x11][..]).count(), 0);
+    }
+
+    #[test]
+    fn test_valord_min_max_key_by_tie_break() {
+        let mut valord = ValordMap::new();
+        assert_eq!(valord.min_key_by(|a: &&str, b| a.cmp(b)), None);
+        assert_eq!(valord.max_key_by(|a: &&str, b| a.cmp(b)), None);
+
+        for (k, score) in [
+            ("mia", 3),
+            ("bob", 1),
+            ("zed", 1),
+            ("amy", 1),
+            ("kim", 3),
+            ("eve", 3),
+            ("ann", 2),
+        ] {
+            valord.insert(k, score);
+        }
+        valord.remove(&"amy");
+
+        let lexicographic = |a: &&str, b: &&str| a.cmp(b);
+        for _ in 0..3 {
+            assert_eq!(valord.min_key_by(lexicographic), Some(&"bob"));
+            assert_eq!(valord.max_key_by(lexicographic), Some(&"eve"));
+        }
+        assert_eq!(valord.max_key_by(|a, b| b.cmp(a)), Some(&"mia"));
+
+        valord.insert("bob", 4);
+        assert_eq!(valord.min_key_by(lexicographic), Some(&"zed"));
+        assert_eq!(valord.max_key_by(lexicographic), Some(&"bob"));
+    }
+
+    #[test]
+    fn test_valord_prewarmed_buckets_are_invisible() {
+        let mut valord = ValordMap::new();
+        valord.prewarm_buckets([1, 3, 5, 7, 9]);
+        let mut cold = ValordMap::new();
+
+        assert!(valord.is_empty());
+        assert!(valord.first().is_empty());
+        assert!(valord.last_mut().is_empty());
+        assert!(valord.to_bucket_deque().is_empty());
+        assert!(valord.bucket_size_histogram().is_empty());
+        assert_eq!(valord.min_key_by(|a: &&str, b| a.cmp(b)), None);
+        assert_eq!(valord, cold);
+        assert_eq!(valord.ordering_fingerprint(), cold.ordering_fingerprint());
+
+        for (k, v) in [("qians", 3), ("tedious", 3), ("xuandu", 5), ("sheng", 4)] {
+            valord.insert(k, OrdByValue::new(0, v));
+            cold.insert(k, OrdByValue::new(0, v));
+        }
+
+        assert_eq!(valord, cold);
+        assert_eq!(valord.ordering_fingerprint(), cold.ordering_fingerprint());
+        assert_eq!(valord.first().len(), 2);
+        assert_eq!(valord.last(), vec![(&"xuandu", &OrdByValue::new(0, 5))]);
+        assert_eq!(valord.max_key_by(|a, b| a.cmp(b)), Some(&"xuandu"));
+        assert_eq!(
+            valord.bucket_size_histogram(),
+            BTreeMap::from([(1, 2), (2, 1)])
+        );
+        assert_eq!(
+            valord
+                .to_bucket_deque()
+                .iter()
+                .map(|(t, _)| *t)
+                .collect::<Vec<_>>(),
+            vec![3, 4, 5]
+        );
+        assert_eq!(
+            valord
+                .iter_ranked()
+                .map(|(rank, _, _)| rank)
+                .collect::<Vec<_>>(),
+            vec![0, 0, 2, 3]
+        );
+
+        valord.adjust_last(|_, v| v.order_by = 8);
+        valord.adjust_first(|_, v| v.order_by = 6);
+        assert_eq!(
+            valord.values().map(|v| v.order_by).collect::<Vec<_>>(),
+            vec![4, 6, 6, 8]
+        );
+        assert_eq!(
+            valord.sorted_indexs.keys().copied().collect::<Vec<_>>(),
+            vec![4, 6, 8]
+        );
+        assert!([3, 5]
             .iter()
-            .enumerate()
-            .filter_map(|(i, (_, v))| v.as_ref().map(|v| (v.ord_by(), i)))
-            .for_each(|(t, i)| {
-                sorted.entry(t).or_default().insert(i);
-            });
-        self.sorted_indexs = sorted;
+            .all(|t| valord.prewarmed[t].is_empty() && valord.prewarmed[t].capacity() > 0));
+
+        valord.remove(&"sheng");
+        valord.insert("qians", OrdByValue::new(0, 3));
+        assert_eq!(valord.first(), vec![(&"qians", &OrdByValue::new(0, 3))]);
+        valord.remove(&"qians");
+        assert!(!valord.sorted_indexs.contains_key(&3));
+        assert!(valord.prewarmed[&3].capacity() > 0);
+        assert!(!valord.prewarmed.contains_key(&4));
+        assert_eq!(
+            valord
+                .into_grouped()
+                .into_iter()
+                .map(|(t, entries)| (t, entries.len()))
+                .collect::<Vec<_>>(),
+            vec![(6, 1), (8, 1)]
+        );
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.len() == 0
+    #[test]
+    fn test_valord_remove_min_max_report_index() {
+        let mut valord = ValordMap::new();
+        (0..8).for_each(|i| valord.insert(i, OrdByValue::new(i, (i * 5) % 8)));
+        valord.remove(&2);
+
+        let mut popped = Vec::new();
+        while !valord.is_empty() {
+            let expected_min = *valord.first()[0].0;
+            let min_index = valord.map.get_index_of(&expected_min).unwrap();
+            let (index, k, v) = valord.remove_min().unwrap();
+            assert_eq!((index, k), (min_index, expected_min));
+            assert_eq!(valord.get(&k), None);
+            popped.push(v.order_by);
+
+            if let Some((&expected_max, _)) = valord.last().first() {
+                let max_index = valord.map.get_index_of(&expected_max).unwrap();
+                let (index, k, v) = valord.remove_max().unwrap();
+                assert_eq!((index, k), (max_index, expected_max));
+                popped.push(v.order_by);
+            }
+        }
+        assert_eq!(popped, vec![0, 7, 1, 6, 3, 5, 4]);
+        assert_eq!(valord.remove_min(), None);
+        assert_eq!(valord.remove_max(), None);
+
+        valord.insert(3, OrdByValue::new(3, 9));
+        assert_eq!(valord.map.len(), 8);
+        assert_eq!(valord.remove_max(), Some((3, 3, OrdByValue::new(3, 9))));
     }
 
-    fn get_by_index(&self, index: usize) -> Option<(&K, &V)> {
-        self.map
-            .get_index(index)
-            .and_then(|(k, maybe_val)| maybe_val.as_ref().map(|v| (k, v)))
+    #[test]
+    fn test_valord_extend_ref_from_borrowed() {
+        let mut valord = ValordMap::new();
+        valord.insert("qians", 5);
+        valord.insert("tedious", 1);
+        valord.insert("xuandu", 3);
+
+        let mut other = ValordMap::new();
+        other.insert("tedious", 6);
+        other.insert("sheng", 2);
+        other.insert("yu", 4);
+        other.remove(&"yu");
+        let other_ref = &other;
+
+        valord.extend_ref(other_ref);
+
+        assert_eq!(
+            valord.iter().collect::<Vec<_>>(),
+            vec![
+                (&"sheng", &2),
+                (&"xuandu", &3),
+                (&"qians", &5),
+                (&"tedious", &6)
+            ]
+        );
+        assert_eq!(
+            other.iter().collect::<Vec<_>>(),
+            vec![(&"sheng", &2), (&"tedious", &6)]
+        );
+
+        valord.extend_ref(&other);
+        assert_eq!(valord.len(), 4);
+        assert_eq!(valord.count_at(&6), 1);
     }
 
-    fn get_mut_by_index(&mut self, index: usize) -> Option<RawEntry<'_, T, K, V>> {
-        RawEntry::try_new_by_index(self, index)
+    #[test]
+    fn test_valord_search_sorted_matches_slice() {
+        let mut valord = ValordMap::new();
+        for (i, v) in [4, 8, 8, 8, 15, 16, 23, 42, 42].into_iter().enumerate() {
+            valord.insert(i, v);
+        }
+        valord.remove(&5);
+        valord.prewarm_buckets([10]);
+
+        let sorted: Vec<_> = valord.values().copied().collect();
+        for target in 0..50 {
+            let expected = match sorted.binary_search(&target) {
+                Ok(_) => Ok(sorted.partition_point(|v| *v < target)),
+                Err(rank) => Err(rank),
+            };
+            assert_eq!(valord.search_sorted(&target), expected, "target {target}");
+        }
+        assert_eq!(valord.search_sorted(&16), Err(5));
+        assert_eq!(valord.search_sorted(&10), Err(4));
+        assert_eq!(valord.search_sorted(&42), Ok(6));
     }
 
-    fn get_full_mut<'a>(
-        map: &'a mut IndexMap<K, Option<V>>,
-        key: &'a K,
-    ) -> Option<(usize, &'a K, &'a mut V)> {
-        map.get_full_mut(key)
-            .and_then(|(i, k, v)| v.as_mut().map(|v| (i, k, v)))
+    #[test]
+    fn test_valord_drain_min_while_stops_at_threshold() {
+        let mut valord = ValordMap::new();
+        for (k, due) in [
+            (1, 50),
+            (2, 10),
+            (3, 40),
+            (4, 10),
+            (5, 30),
+            (6, 20),
+            (7, 60),
+        ] {
+            valord.insert(k, OrdByValue::new(k, due));
+        }
+        valord.remove(&6);
+
+        let mut seen = 0;
+        let drained = valord.drain_min_while(|_, v| {
+            seen += 1;
+            v.order_by < 45
+        });
+
+        assert_eq!(seen, 5);
+        assert_eq!(
+            drained.iter().map(|(_, v)| v.order_by).collect::<Vec<_>>(),
+            vec![10, 10, 30, 40]
+        );
+        let mut keys: Vec<_> = drained.iter().map(|(k, _)| *k).collect();
+        keys.sort();
+        assert_eq!(keys, vec![2, 3, 4, 5]);
+        assert_eq!(valord.len(), 2);
+        assert_eq!(valord.first(), vec![(&1, &OrdByValue::new(1, 50))]);
+
+        assert!(valord.drain_min_while(|_, _| false).is_empty());
+        assert_eq!(valord.drain_min_while(|_, _| true).len(), 2);
+        assert!(valord.is_empty());
+        assert!(valord.drain_min_while(|_, _| true).is_empty());
     }
 
-    fn iter_from_indexs<'a>(
-        &'a self,
-        indexs: &'a HashSet<usize>,
-    ) -> impl Iterator<Item = (&K, &V)> {
-        indexs.iter().filter_map(|index| self.get_by_index(*index))
+    #[test]
+    fn test_valord_computed_ord_by_without_stored_key() {
+        struct Word(&'static str);
+
+        impl OrdBy for Word {
+            type Target = (usize, &'static str);
+
+            fn ord_by(&self) -> Self::Target {
+                (self.0.len(), self.0)
+            }
+        }
+
+        let mut valord = ValordMap::new();
+        valord.insert(1, Word("pear"));
+        valord.insert(2, Word("fig"));
+        valord.insert(3, Word("banana"));
+        valord.insert(4, Word("kiwi"));
+
+        assert_eq!(
+            valord.values().map(|w| w.0).collect::<Vec<_>>(),
+            vec!["fig", "kiwi", "pear", "banana"]
+        );
+        assert_eq!(valord.range((4, "")..(5, "")).count(), 2);
+
+        valord.get_mut(&2).unwrap().0 = "cherry";
+        assert_eq!(
+            valord.rev_values().map(|w| w.0).collect::<Vec<_>>(),
+            vec!["cherry", "banana", "pear", "kiwi"]
+        );
+        assert_eq!(valord.count_at(&(3, "fig")), 0);
     }
 
-    fn iter_mut_from_indexs<'a>(
-        valord: *mut ValordMap<T, K, V>,
-        indexs: HashSet<usize>,
-    ) -> impl Iterator<Item = RawEntry<'a, T, K, V>>
-    where
-        T: 'a,
-        K: 'a,
-        V: 'a,
-    {
-        indexs.into_iter().filter_map(move |index| {
-            let vm = unsafe { valord.as_mut()? };
-            vm.get_mut_by_index(index)
-        })
+    #[test]
+    fn test_valord_retain_compact_half() {
+        let mut valord = ValordMap::new();
+        (0..20).for_each(|i| valord.insert(i, OrdByValue::new(i, 20 - i % 10)));
+        valord.remove(&1);
+        valord.remove(&2);
+        let old_indexes: Vec<_> = (0..20).map(|i| valord.map.get_index_of(&i)).collect();
+
+        let moved = valord.retain_compact(|k, _| k % 2 == 0);
+
+        assert_eq!(valord.len(), 9);
+        assert_eq!(valord.map.len(), 9);
+        assert!(valord.free_indexs.is_empty());
+        assert_eq!(moved.len(), 9);
+        for k in (0..20).filter(|k| k % 2 == 0 && *k != 2) {
+            let old = old_indexes[k].unwrap();
+            assert_eq!(moved.get(&old), valord.map.get_index_of(&k).as_ref());
+        }
+        let mut new_indexes: Vec<_> = moved.values().copied().collect();
+        new_indexes.sort();
+        assert_eq!(new_indexes, (0..9).collect::<Vec<_>>());
+
+        assert_eq!(
+            valord.values().map(|v| v.order_by).collect::<Vec<_>>(),
+            vec![12, 12, 14, 14, 16, 16, 18, 20, 20]
+        );
+        assert_eq!(valord.first().len(), 2);
+        assert_eq!(
+            valord.range(17..19).collect::<Vec<_>>(),
+            vec![(&12, &OrdByValue::new(12, 18))]
+        );
+        valord.insert(2, OrdByValue::new(2, 1));
+        assert_eq!(valord.map.get_index_of(&2), Some(9));
     }
 
-    fn remove_from_indexs(sorted_indexs: &mut BTreeMap<T, HashSet<usize>>, key: &T, index: usize) {
-        if let Some(indexs) = sorted_indexs.get_mut(key) {
-            indexs.remove(&index);
-            if indexs.is_empty() {
-                sorted_indexs.remove(key);
+    #[test]
+    fn test_valord_merge_recompute_sums_change_ranks() {
+        let mut valord = ValordMap::new();
+        valord.insert("qians", 10);
+        valord.insert("tedious", 20);
+        valord.insert("xuandu", 30);
+        valord.insert("sheng", 5);
+        valord.remove(&"sheng");
+
+        let mut other = ValordMap::new();
+        other.insert("qians", 25);
+        other.insert("sheng", 1);
+        other.insert("yu", 15);
+        other.insert("tedious", 0);
+
+        valord.merge_recompute(other, |a, b| a + b);
+
+        assert_eq!(
+            valord.iter().collect::<Vec<_>>(),
+            vec![
+                (&"sheng", &1),
+                (&"yu", &15),
+                (&"tedious", &20),
+                (&"xuandu", &30),
+                (&"qians", &35)
+            ]
+        );
+        assert_eq!(valord.len(), 5);
+        assert_eq!(valord.count_at(&10), 0);
+        assert!(valord.free_indexs.is_empty());
+
+        let mut other = ValordMap::new();
+        other.insert("xuandu", 1);
+        valord.merge_recompute(other, |a, b| a.min(b));
+        assert_eq!(valord.first().len(), 2);
+    }
+
+    #[test]
+    fn test_valord_nth_from_end_positions() {
+        let mut valord = ValordMap::new();
+        for (k, v) in [
+            ("a", 5),
+            ("b", 9),
+            ("c", 9),
+            ("d", 1),
+            ("e", 7),
+            ("f", 9),
+            ("g", 3),
+        ] {
+            valord.insert(k, v);
+        }
+        valord.remove(&"f");
+        valord.prewarm_buckets([10, 8]);
+
+        let descending: Vec<_> = valord.rev_values().copied().collect();
+        assert_eq!(descending, vec![9, 9, 7, 5, 3, 1]);
+        for (n, expected) in descending.iter().enumerate() {
+            assert_eq!(valord.nth_from_end(n).map(|(_, v)| v), Some(expected));
+        }
+
+        let mut tied: Vec<_> = (0..2).map(|n| *valord.nth_from_end(n).unwrap().0).collect();
+        tied.sort();
+        assert_eq!(tied, vec!["b", "c"]);
+        assert_eq!(valord.nth_from_end(5), Some((&"d", &1)));
+        assert_eq!(valord.nth_from_end(6), None);
+        assert_eq!(valord.nth_from_end(usize::MAX), None);
+        assert_eq!(ValordMap::<u8, u8, u8>::new().nth_from_end(0), None);
+    }
+
+    #[test]
+    fn test_running_order_stats_streaming_median() {
+        let mut stats = RunningOrderStats::new();
+        let mut seen = Vec::new();
+        for v in [41, 7, 7, 93, 15, 62, 7, 100, 0, 58, 33, 33, 76, 2] {
+            stats.insert(OrdByValue::new(seen.len(), v));
+            seen.push(v);
+            seen.sort();
+
+            let lower_median = seen[(seen.len() - 1) / 2];
+            assert_eq!(stats.len(), seen.len());
+            assert_eq!(stats.median().map(|v| v.order_by), Some(lower_median));
+            for p in [0.0, 10.0, 50.0, 75.0, 99.9, 100.0] {
+                let rank = ((p / 100.0 * seen.len() as f64).ceil() as usize).max(1) - 1;
+                assert_eq!(stats.percentile(p).map(|v| v.order_by), Some(seen[rank]));
             }
         }
+        assert!(stats.percentile(f64::NAN).is_none());
+        assert!(stats.percentile(-1.0).is_none());
+        assert!(RunningOrderStats::<u8>::new().percentile(50.0).is_none());
     }
-}
 
-impl<T, K, V> Default for ValordMap<T, K, V>
-where
-    T: Ord + Clone,
-    K: std::hash::Hash + Eq,
-    V: OrdBy<Target = T>,
-{
-    fn default() -> Self {
-        Self::new()
+    #[test]
+    fn test_key_ordered_iterates_in_key_order() {
+        struct NotOrd(&'static str);
+
+        let mut map = ValordMap::by_key();
+        for k in [42, 7, 19, 3, 88, 7, 56] {
+            map.insert(k, NotOrd("x"));
+        }
+        assert_eq!(map.insert(19, NotOrd("nineteen")).map(|v| v.0), Some("x"));
+        assert_eq!(map.remove(&88).map(|v| v.0), Some("x"));
+        map.get_mut(&3).unwrap().0 = "three";
+
+        assert_eq!(map.len(), 5);
+        assert_eq!(
+            map.iter().map(|(k, _)| *k).collect::<Vec<_>>(),
+            vec![3, 7, 19, 42, 56]
+        );
+        assert_eq!(
+            map.rev_iter().map(|(k, _)| *k).collect::<Vec<_>>(),
+            vec![56, 42, 19, 7, 3]
+        );
+        assert_eq!(map.first().map(|(k, v)| (*k, v.0)), Some((3, "three")));
+        assert_eq!(map.last().map(|(k, _)| *k), Some(56));
+        assert_eq!(
+            map.range(7..=42)
+                .map(|(k, v)| (*k, v.0))
+                .collect::<Vec<_>>(),
+            vec![(7, "x"), (19, "nineteen"), (42, "x")]
+        );
+        assert!(!map.contains_key(&88));
+        map.insert(88, NotOrd("back"));
+        assert_eq!(map.last().map(|(k, v)| (*k, v.0)), Some((88, "back")));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::cell::Cell;
+    #[test]
+    fn test_valord_drain_range_into_appends_window() {
+        let mut valord = ValordMap::new();
+        (0..10).for_each(|i| valord.insert(i, OrdByValue::new(i, (i * 3) % 10)));
+        valord.insert(10, OrdByValue::new(10, 4));
+        valord.remove(&9);
 
-    use super::*;
+        let mut sink = vec![(100, OrdByValue::new(100, 99))];
+        valord.drain_range_into(3..=6, &mut sink);
 
-    #[derive(Debug, PartialEq, Eq)]
-    struct OrdByValue {
-        sth: usize,
-        order_by: usize,
+        assert_eq!(sink[0], (100, OrdByValue::new(100, 99)));
+        assert_eq!(
+            sink[1..]
+                .iter()
+                .map(|(_, v)| v.order_by)
+                .collect::<Vec<_>>(),
+            vec![3, 4, 4, 5, 6]
+        );
+        let mut keys: Vec<_> = sink[1..].iter().map(|(k, _)| *k).collect();
+        keys.sort();
+        assert_eq!(keys, vec![1, 2, 5, 8, 10]);
+
+        assert_eq!(valord.len(), 5);
+        assert_eq!(valord.range_count(3..=6), 0);
+        assert_eq!(
+            valord.values().map(|v| v.order_by).collect::<Vec<_>>(),
+            vec![0, 1, 2, 8, 9]
+        );
+        assert_eq!(valord.free_indexs.len(), 6);
+
+        let len = sink.len();
+        valord.drain_range_into(20.., &mut sink);
+        assert_eq!(sink.len(), len);
+        valord.insert(5, OrdByValue::new(5, 5));
+        assert_eq!(valord.map.get_index_of(&5), Some(5));
     }
 
-    impl OrdByValue {
-        fn new(sth: usize, order_by: usize) -> Self {
-            Self { sth, order_by }
+    #[test]
+    fn test_valord_modify_and_rank_reports_new_rank() {
+        let mut valord = ValordMap::new();
+        for (k, v) in [("a", 10), ("b", 20), ("c", 20), ("d", 30), ("e", 40)] {
+            valord.insert(k, OrdByValue::new(0, v));
+        }
+
+        assert_eq!(valord.modify_and_rank(&"a", |v| v.order_by = 35), Some(3));
+        assert_eq!(valord.modify_and_rank(&"e", |v| v.order_by = 1), Some(0));
+        assert_eq!(valord.modify_and_rank(&"d", |v| v.order_by = 20), Some(1));
+        assert_eq!(valord.modify_and_rank(&"b", |v| v.sth = 7), Some(1));
+
+        let ranks: Vec<_> = valord
+            .iter_ranked()
+            .map(|(rank, k, _)| (*k, rank))
+            .collect();
+        for (k, rank) in ranks {
+            assert_eq!(valord.modify_and_rank(&k, |_| {}), Some(rank));
         }
+        assert_eq!(valord.last(), vec![(&"a", &OrdByValue::new(0, 35))]);
+
+        valord.remove(&"a");
+        assert_eq!(valord.modify_and_rank(&"a", |v| v.order_by = 0), None);
+        assert_eq!(valord.len(), 4);
     }
 
-    impl OrdBy for OrdByValue {
-        type Target = usize;
+    #[test]
+    fn test_valord_drain_all_but_top_k() {
+        let mut valord = ValordMap::new();
+        (0..10).for_each(|i| valord.insert(i, OrdByValue::new(i, (i * 7) % 10)));
+        let capacity = valord.map.capacity();
 
-        fn ord_by(&self) -> Self::Target {
-            self.order_by
+        let evicted = valord.drain_all_but_top_k(3);
+
+        assert_eq!(evicted.len(), 7);
+        assert_eq!(
+            evicted.iter().map(|(_, v)| v.order_by).collect::<Vec<_>>(),
+            (0..7).collect::<Vec<_>>()
+        );
+        assert!(evicted.iter().all(|(k, v)| v.sth == *k));
+        assert_eq!(
+            valord.values().map(|v| v.order_by).collect::<Vec<_>>(),
+            vec![7, 8, 9]
+        );
+        assert_eq!(valord.map.capacity(), capacity);
+
+        assert!(valord.drain_all_but_top_k(3).is_empty());
+        assert!(valord.drain_all_but_top_k(10).is_empty());
+        assert_eq!(valord.drain_all_but_top_k(0).len(), 3);
+        assert!(valord.is_empty());
+    }
+
+    #[test]
+    fn test_check_ord_by_detects_broken_impls() {
+        __assert_ord_by_sound::<OrdByValue>();
+        __assert_ord_by_sound::<Score>();
+
+        let samples: Vec<_> = [3, 1, 4, 1, 5, 9, 2, 6]
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| OrdByValue::new(i, v))
+            .collect();
+        assert!(check_ord_by(&samples));
+        assert!(check_ord_by::<OrdByValue>(&[]));
+
+        // Each call hands out a fresh ordering value.
+        struct Drifting(Cell<u32>);
+
+        impl OrdBy for Drifting {
+            type Target = u32;
+
+            fn ord_by(&self) -> u32 {
+                self.0.set(self.0.get() + 1);
+                self.0.get()
+            }
+        }
+
+        assert!(!check_ord_by(&[Drifting(Cell::new(0))]));
+
+        // An `Ord` that is not antisymmetric: everything is "less" than everything.
+        #[derive(Clone, PartialEq, Eq)]
+        struct AlwaysLess(u8);
+
+        impl PartialOrd for AlwaysLess {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for AlwaysLess {
+            fn cmp(&self, _: &Self) -> std::cmp::Ordering {
+                std::cmp::Ordering::Less
+            }
         }
+
+        assert!(!check_ord_by(&[AlwaysLess(1), AlwaysLess(2)]));
     }
 
     #[test]
-    fn test_valord_insert_order_by() {
+    fn test_valord_snapshot_unaffected_by_mutation() {
         let mut valord = ValordMap::new();
-        valord.insert("qians", OrdByValue::new(123, 1));
-        valord.insert("tedious", OrdByValue::new(412, 2));
-        valord.insert("xuandu", OrdByValue::new(125, 3));
-        valord.insert("xuandu", OrdByValue::new(938, 1));
+        (0..6).for_each(|i| valord.insert(i, OrdByValue::new(i, 10 - i)));
+        valord.remove(&5);
 
-        let sorted_pairs: Vec<_> = valord.iter().collect();
+        let snapshot = valord.snapshot();
+        let shared = snapshot.clone();
+        let before: Vec<_> = snapshot.iter().map(|(k, v)| (*k, v.order_by)).collect();
 
-        assert_eq!(sorted_pairs.len(), 3);
-        assert_eq!(sorted_pairs[0].1.order_by, 1);
-        assert_eq!(sorted_pairs[1].1.order_by, 1);
-        assert_eq!(sorted_pairs[2], (&"tedious", &OrdByValue::new(412, 2)));
+        valord.insert(0, OrdByValue::new(0, 0));
+        valord.remove(&3);
+        valord.insert(9, OrdByValue::new(9, 99));
+        valord.get_mut(&1).unwrap().order_by = 50;
+        valord.shrink_to_fit();
+
+        assert_eq!(
+            snapshot
+                .iter()
+                .map(|(k, v)| (*k, v.order_by))
+                .collect::<Vec<_>>(),
+            before
+        );
+        assert_eq!(before, vec![(4, 6), (3, 7), (2, 8), (1, 9), (0, 10)]);
+        assert!(snapshot.get(&9).is_none());
+        assert_eq!(valord.len(), 5);
+        assert_eq!(valord.last(), vec![(&9, &OrdByValue::new(9, 99))]);
+
+        let from_thread =
+            std::thread::spawn(move || shared.iter().map(|(k, _)| *k).collect::<Vec<_>>())
+                .join()
+                .unwrap();
+        assert_eq!(from_thread, vec![4, 3, 2, 1, 0]);
     }
 
     #[test]
-    fn test_valord_remove_non_existent() {
+    fn test_valord_delete_range_reports_freed_indexes() {
         let mut valord = ValordMap::new();
-        valord.insert(1, "a");
-        valord.insert(2, "b");
+        (0..12).for_each(|i| valord.insert(i * 10, OrdByValue::new(i, (i * 5) % 12)));
+        valord.remove(&30);
 
-        let removed_value = valord.remove(&3);
-        assert_eq!(removed_value, None);
-        assert_eq!(valord.get(&3), None);
+        let mut expected: Vec<_> = valord
+            .range(4..9)
+            .map(|(k, _)| valord.map.get_index_of(k).unwrap())
+            .collect();
+        let (count, mut freed) = valord.delete_range(4..9);
+
+        assert_eq!(count, 5);
+        assert_eq!(freed.len(), count);
+        expected.sort();
+        freed.sort();
+        assert_eq!(freed, expected);
+        assert_eq!(freed, vec![1, 4, 6, 8, 11]);
+
+        assert_eq!(valord.len(), 6);
+        assert_eq!(valord.range_count(4..9), 0);
+        assert!(freed.iter().all(|index| valord.free_indexs.contains(index)));
+        assert_eq!(valord.delete_range(..).0, 6);
+        assert_eq!(valord.free_indexs.len(), 12);
+        assert!(valord.is_empty());
     }
 
     #[test]
-    fn test_valord_multiple_insert_and_remove() {
+    fn test_valord_first_last_or_insert_with() {
         let mut valord = ValordMap::new();
-        valord.insert("qians", 1);
-        valord.insert("tedious", 2);
-        valord.insert("xuandu", 3);
+        valord.prewarm_buckets([1, 2]);
 
-        assert_eq!(valord.remove(&"tedious"), Some(2));
-        assert_eq!(valord.remove(&"qians"), Some(1));
+        let made = Cell::new(0);
+        let make = |order_by| {
+            made.set(made.get() + 1);
+            OrdByValue::new(made.get(), order_by)
+        };
 
-        valord.insert("x", 2);
-        valord.insert("y", 4);
+        assert_eq!(
+            valord.first_or_insert_with("a", || make(5)),
+            (&"a", &OrdByValue::new(1, 5))
+        );
+        assert_eq!(
+            valord.last_or_insert_with("b", || make(7)),
+            (&"a", &OrdByValue::new(1, 5))
+        );
+        assert_eq!(made.get(), 1);
 
-        let sorted_pairs: Vec<_> = valord.iter().collect();
-        println!("sorted_pairs: {sorted_pairs:?}");
-        assert_eq!(sorted_pairs.len(), 3);
-        assert_eq!(sorted_pairs[0], (&"x", &2));
-        assert_eq!(sorted_pairs[1], (&"xuandu", &3));
-        assert_eq!(sorted_pairs[2], (&"y", &4));
+        valord.insert("c", OrdByValue::new(0, 9));
+        valord.insert("d", OrdByValue::new(0, 1));
+        assert_eq!(valord.first_or_insert_with("e", || make(0)).0, &"d");
+        assert_eq!(valord.last_or_insert_with("e", || make(0)).0, &"c");
+        assert_eq!(made.get(), 1);
+        assert_eq!(valord.len(), 3);
+
+        valord.delete_range(..);
+        assert_eq!(
+            valord.last_or_insert_with("a", || make(3)),
+            (&"a", &OrdByValue::new(2, 3))
+        );
+        assert_eq!(valord.len(), 1);
+    }
+
+    #[test]
+    fn test_valord_keys_in_bucket_range_large_tie() {
+        let mut valord = ValordMap::new();
+        (0..500).for_each(|i| valord.insert(i, OrdByValue::new(i, i % 2)));
+        (100..120).for_each(|i| {
+            valord.remove(&i);
+        });
+
+        let odd: Vec<_> = valord.keys_in_bucket_range(&1, 95..=125).copied().collect();
+        assert_eq!(odd, vec![95, 97, 99, 121, 123, 125]);
+
+        let even: Vec<_> = valord.keys_in_bucket_range(&0, ..10).copied().collect();
+        assert_eq!(even, vec![0, 2, 4, 6, 8]);
+
+        assert_eq!(valord.keys_in_bucket_range(&0, ..).count(), 240);
+        assert_eq!(valord.keys_in_bucket_range(&0, 100..120).count(), 0);
+        assert_eq!(valord.keys_in_bucket_range(&2, ..).count(), 0);
+    }
+
+    #[test]
+    fn test_valord_retain_stable_keeps_indexes() {
+        let mut valord = ValordMap::new();
+        (0..16).for_each(|i| valord.insert(i, OrdByValue::new(i, (i * 11) % 16)));
+        valord.remove(&3);
+        let before: Vec<_> = (0..16).map(|i| valord.map.get_index_of(&i)).collect();
+
+        valord.retain_stable(|k, v| k % 3 != 0 && v.order_by > 2);
+
+        let survivors: Vec<_> = valord.keys().copied().collect();
+        assert_eq!(survivors.len(), 10);
+        for k in &survivors {
+            assert_eq!(valord.map.get_index_of(k), before[*k]);
+        }
+        assert_eq!(valord.map.len(), 16);
+        assert_eq!(valord.free_indexs.len(), 6);
+        let order_bys: Vec<_> = valord.values().map(|v| v.order_by).collect();
+        let mut sorted = order_bys.clone();
+        sorted.sort();
+        assert_eq!(order_bys, sorted);
+
+        // A removed key gets its own slot back; a new key takes a freed one.
+        valord.insert(3, OrdByValue::new(3, 1));
+        assert_eq!(valord.map.get_index_of(&3), before[3]);
+        valord.insert(16, OrdByValue::new(16, 0));
+        assert!(valord.map.get_index_of(&16) < Some(16));
+        assert_eq!(valord.map.len(), 16);
+        assert_eq!(valord.free_indexs.len(), 4);
+        for k in &survivors {
+            assert_eq!(valord.map.get_index_of(k), before[*k]);
+        }
     }
 
     #[test]
@@ -1,3 +1,39 @@
+use std::cmp::Reverse;
+
+/// Gives the value a [`ValordMap`][crate::ValordMap] sorts an entry by.
+///
+/// `ord_by` returns an owned `Target`, so it can be computed on the fly instead
+/// of being stored in the value. It is called again whenever the map re-sorts an
+/// entry, so keep it cheap and consistent with the value's contents. Call
+/// [`check_ord_by`] from a unit test to sanity-check an impl.
+///
+/// Every `T: Ord + Clone` implements `OrdBy` with itself as the target.
+///
+/// # Example
+///
+/// ```
+/// use valord_map::{OrdBy, ValordMap};
+///
+/// struct Rect {
+///     width: u32,
+///     height: u32,
+/// }
+///
+/// // Ordered by area, without keeping an `area` field around.
+/// impl OrdBy for Rect {
+///     type Target = u32;
+///
+///     fn ord_by(&self) -> u32 {
+///         self.width * self.height
+///     }
+/// }
+///
+/// let mut valord = ValordMap::new();
+/// valord.insert("wide", Rect { width: 8, height: 1 });
+/// valord.insert("square", Rect { width: 2, height: 2 });
+///
+/// assert_eq!(valord.keys().collect::<Vec<_>>(), vec![&"square", &"wide"]);
+/// ```
 pub trait OrdBy {
     type Target: Ord + Clone;
     fn ord_by(&self) -> Self::Target;
@@ -9,3 +45,112 @@ impl<T: Ord + Clone> OrdBy for T {
         self.clone()
     }
 }
+
+/// Checks that `V`'s `ord_by()` behaves the way [`ValordMap`][crate::ValordMap]
+/// relies on, over the given sample values. Meant to be called from a unit test
+/// of an `OrdBy` impl.
+///
+/// For every sample, calling `ord_by()` twice and cloning the target must give
+/// equal targets. For every pair and triple of targets, `cmp` must be
+/// antisymmetric, transitive and agree with `==` and `partial_cmp`. The cost is
+/// cubic in the number of samples, so keep the samples small.
+///
+/// # Example
+///
+/// ```
+/// use valord_map::{check_ord_by, OrdBy};
+///
+/// struct Task {
+///     priority: u8,
+///     name: &'static str,
+/// }
+///
+/// impl OrdBy for Task {
+///     type Target = (u8, &'static str);
+///
+///     fn ord_by(&self) -> Self::Target {
+///         (self.priority, self.name)
+///     }
+/// }
+///
+/// let samples = [
+///     Task { priority: 2, name: "b" },
+///     Task { priority: 1, name: "z" },
+///     Task { priority: 2, name: "a" },
+/// ];
+/// assert!(check_ord_by(&samples));
+/// ```
+pub fn check_ord_by<V: OrdBy>(samples: &[V]) -> bool {
+    let targets: Vec<_> = samples.iter().map(OrdBy::ord_by).collect();
+
+    let stable = samples.iter().zip(&targets).all(|(sample, target)| {
+        let again = sample.ord_by();
+        let cloned = target.clone();
+        again == *target && again.cmp(target).is_eq() && cloned == *target
+    });
+
+    let consistent = targets.iter().all(|a| {
+        targets.iter().all(|b| {
+            let ord = a.cmp(b);
+            ord == b.cmp(a).reverse()
+                && a.partial_cmp(b) == Some(ord)
+                && ord.is_eq() == (a == b)
+                && targets
+                    .iter()
+                    .all(|c| !(ord.is_le() && b <= c) || a.cmp(c).is_le())
+        })
+    });
+
+    stable && consistent
+}
+
+/// Fails to compile unless `V` implements `OrdBy` with an `Ord + Clone` target.
+#[doc(hidden)]
+pub fn __assert_ord_by_sound<V: OrdBy>() {
+    fn assert_target<T: Ord + Clone>() {}
+    assert_target::<V::Target>();
+}
+
+/// How a [`ValordMap`][crate::ValordMap] gets the ordering value of an entry.
+///
+/// This is the map's last type parameter, so the call is statically dispatched and
+/// usually inlined. [`ByOrdBy`] (the default) and [`ByOrdByDesc`] use the value's
+/// [`OrdBy`] impl; any `Fn(&V) -> &T` orders by the field it returns, see
+/// [`ValordMap::new_by`][crate::ValordMap::new_by].
+pub trait OrdByFn<V, T> {
+    fn ord_by(&self, value: &V) -> T;
+}
+
+/// Orders entries by [`OrdBy::ord_by`], as [`ValordMap::new`][crate::ValordMap::new] does.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ByOrdBy;
+
+impl<V: OrdBy> OrdByFn<V, V::Target> for ByOrdBy {
+    #[inline]
+    fn ord_by(&self, value: &V) -> V::Target {
+        value.ord_by()
+    }
+}
+
+/// Orders entries by `Reverse(value.ord_by())`, as
+/// [`ValordMap::new_desc`][crate::ValordMap::new_desc] does.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ByOrdByDesc;
+
+impl<V: OrdBy> OrdByFn<V, Reverse<V::Target>> for ByOrdByDesc {
+    #[inline]
+    fn ord_by(&self, value: &V) -> Reverse<V::Target> {
+        Reverse(value.ord_by())
+    }
+}
+
+impl<V, T, F> OrdByFn<V, T> for F
+where
+    T: Clone,
+    F: Fn(&V) -> &T,
+{
+    #[inline]
+    fn ord_by(&self, value: &V) -> T {
+        self(value).clone()
+    }
+}
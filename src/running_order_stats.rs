@@ -0,0 +1,144 @@
+use crate::{OrdBy, ValordMap};
+
+/// Running order statistics (median, percentiles) over a stream of values, order
+/// by value.ord_by().
+///
+/// Values are split over two [`ValordMap`]s: a lower half whose maximum is the
+/// median, and an upper half. Each insert moves at most one value between them.
+///
+/// # Example
+///
+/// ```
+/// use valord_map::RunningOrderStats;
+///
+/// let mut stats = RunningOrderStats::new();
+/// for v in [5, 1, 9, 3] {
+///     stats.insert(v);
+/// }
+///
+/// assert_eq!(stats.len(), 4);
+/// assert_eq!(stats.median(), Some(&3));
+/// assert_eq!(stats.percentile(100.0), Some(&9));
+/// ```
+pub struct RunningOrderStats<V: OrdBy> {
+    lower: ValordMap<V::Target, u64, V>,
+    upper: ValordMap<V::Target, u64, V>,
+    next_id: u64,
+}
+
+impl<V: OrdBy> RunningOrderStats<V> {
+    pub fn new() -> Self {
+        Self {
+            lower: ValordMap::new(),
+            upper: ValordMap::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Add a value to the stream.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::RunningOrderStats;
+    ///
+    /// let mut stats = RunningOrderStats::new();
+    /// stats.insert(2);
+    /// stats.insert(1);
+    ///
+    /// assert_eq!(stats.median(), Some(&1));
+    /// ```
+    pub fn insert(&mut self, value: V) {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let to_lower = match self.lower.nth_from_end(0) {
+            Some((_, max)) => value.ord_by() <= max.ord_by(),
+            None => true,
+        };
+        if to_lower {
+            self.lower.insert(id, value);
+        } else {
+            self.upper.insert(id, value);
+        }
+
+        // Keep the lower half equal to, or one larger than, the upper half.
+        if self.lower.len() > self.upper.len() + 1 {
+            if let Some((_, id, value)) = self.lower.remove_max() {
+                self.upper.insert(id, value);
+            }
+        } else if self.upper.len() > self.lower.len() {
+            if let Some((_, id, value)) = self.upper.remove_min() {
+                self.lower.insert(id, value);
+            }
+        }
+    }
+
+    /// Return the median, or the lower of the two middle values for an even count.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::RunningOrderStats;
+    ///
+    /// let mut stats = RunningOrderStats::new();
+    /// assert_eq!(stats.median(), None);
+    ///
+    /// stats.extend([4, 8, 1]);
+    /// assert_eq!(stats.median(), Some(&4));
+    /// ```
+    pub fn median(&self) -> Option<&V> {
+        self.lower.nth_from_end(0).map(|(_, v)| v)
+    }
+
+    /// Return the `p`-th percentile (`0.0..=100.0`) by the nearest-rank method, or
+    /// `None` if the stream is empty or `p` is out of range.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use valord_map::RunningOrderStats;
+    ///
+    /// let mut stats = RunningOrderStats::new();
+    /// stats.extend(1..=10);
+    ///
+    /// assert_eq!(stats.percentile(0.0), Some(&1));
+    /// assert_eq!(stats.percentile(25.0), Some(&3));
+    /// assert_eq!(stats.percentile(90.0), Some(&9));
+    /// assert_eq!(stats.percentile(101.0), None);
+    /// ```
+    pub fn percentile(&self, p: f64) -> Option<&V> {
+        if self.is_empty() || !(0.0..=100.0).contains(&p) {
+            return None;
+        }
+        let rank = ((p / 100.0 * self.len() as f64).ceil() as usize).max(1) - 1;
+        let found = if rank < self.lower.len() {
+            self.lower.nth_from_end(self.lower.len() - 1 - rank)
+        } else {
+            self.upper.nth_from_end(self.len() - 1 - rank)
+        };
+        found.map(|(_, v)| v)
+    }
+
+    /// Return the number of values inserted so far.
+    pub fn len(&self) -> usize {
+        self.lower.len() + self.upper.len()
+    }
+
+    /// Return `true` if no value was inserted yet.
+    pub fn is_empty(&self) -> bool {
+        self.lower.is_empty()
+    }
+}
+
+impl<V: OrdBy> Default for RunningOrderStats<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: OrdBy> Extend<V> for RunningOrderStats<V> {
+    fn extend<I: IntoIterator<Item = V>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|v| self.insert(v));
+    }
+}
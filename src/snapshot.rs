@@ -0,0 +1,44 @@
+use crate::{ByOrdBy, OrdByFn, ValordMap};
+
+use std::hash::Hash;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// An immutable, cheaply cloneable view of a [`ValordMap`] at the time
+/// [`snapshot`](ValordMap::snapshot) was called.
+///
+/// Every read-only method of the map is available through [`Deref`]. Cloning a
+/// snapshot only bumps a reference count, and a snapshot can be shared across
+/// threads when `T`, `K` and `V` allow it.
+pub struct ValordSnapshot<T, K, V, O = ByOrdBy> {
+    inner: Arc<ValordMap<T, K, V, O>>,
+}
+
+impl<T, K, V, O> ValordSnapshot<T, K, V, O> {
+    pub(crate) fn new(valord: ValordMap<T, K, V, O>) -> Self {
+        Self {
+            inner: Arc::new(valord),
+        }
+    }
+}
+
+impl<T, K, V, O> Clone for ValordSnapshot<T, K, V, O> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T, K, V, O> Deref for ValordSnapshot<T, K, V, O>
+where
+    T: Ord + Clone,
+    K: Hash + Eq,
+    O: OrdByFn<V, T>,
+{
+    type Target = ValordMap<T, K, V, O>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}